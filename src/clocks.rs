@@ -18,15 +18,40 @@
 // IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-use crate::gpio::{self, gpioa};
+use crate::gpio::{self, gpioa, gpioc};
 use crate::time::{Hertz, U32Ext};
 
-use mkl25z4::{MCG, OSC0, PORTA, SIM};
+use mkl25z4::{MCG, OSC0, RCM, SIM};
+
+/// Maximum core/system clock frequency supported by the chip.
+const MAX_CORE_CLOCK: u32 = 48_000_000;
+/// Maximum bus/flash clock frequency supported by the chip.
+const MAX_BUS_CLOCK: u32 = 24_000_000;
+/// Nominal frequency of the slow internal reference clock.
+const SLOW_IRC: u32 = 32_768;
+/// Nominal frequency of the fast internal reference clock.
+const FAST_IRC: u32 = 4_000_000;
+/// Frequency of the low power oscillator.
+const LPO: u32 = 1_000;
+/// Core clock divider (SIM_CLKDIV1[OUTDIV1]) after a reset.
+const RESET_OUTDIV1: u8 = 0;
+/// Bus clock divider (SIM_CLKDIV1[OUTDIV4]) after a reset.
+const RESET_OUTDIV4: u8 = 1;
 
 pub struct ClockConfiguration {
     core_clock: Option<Hertz>,
     bus_clock: Option<Hertz>,
     clock_source: ClockSource,
+    crystal_load: u8,
+    crystal_high_gain: bool,
+    mcgirclk: Option<InternalReference>,
+    oscerclk: Option<bool>,
+    erclk32k: Erclk32kSource,
+    clock_monitor: Option<LossAction>,
+    loss_of_lock: Option<LossAction>,
+    irc_trim: Option<IrcTrim>,
+    slow_irc: Hertz,
+    fast_irc: Hertz,
 }
 
 impl ClockConfiguration {
@@ -35,9 +60,33 @@ impl ClockConfiguration {
             core_clock: None,
             bus_clock: None,
             clock_source: ClockSource::Internal,
+            crystal_load: 0,
+            crystal_high_gain: false,
+            mcgirclk: None,
+            oscerclk: None,
+            erclk32k: Erclk32kSource::Lpo,
+            clock_monitor: None,
+            loss_of_lock: None,
+            irc_trim: None,
+            slow_irc: SLOW_IRC.hz(),
+            fast_irc: FAST_IRC.hz(),
         }
     }
 
+    /// Default configuration for the FRDM-KL25Z board.
+    ///
+    /// The 8 MHz crystal is used as the reference for the PLL, resulting in a 48 MHz core clock
+    /// and a 24 MHz bus clock.
+    pub fn pll_48mhz_from_8mhz_crystal(
+        extal: gpioa::PA18<gpio::Analog>,
+        xtal: gpioa::PA19<gpio::Analog>,
+    ) -> Self {
+        Self::new()
+            .use_crystal(8.mhz(), extal, xtal)
+            .core_clock(48.mhz())
+            .bus_clock(24.mhz())
+    }
+
     pub fn core_clock<T: Into<Hertz>>(mut self, freq: T) -> Self {
         self.core_clock = Some(freq.into());
         self
@@ -51,12 +100,29 @@ impl ClockConfiguration {
     /// Use one of the internal RC oscillators as the main clock source.
     ///
     /// Do not select frequencies near the maximum supported by the chip as the resulting frequency
-    /// can be up to 20% higher than requested when using the internal reference clock.
+    /// can be up to 20% higher than requested when using the internal reference clock. Use
+    /// `trim_irc()` with previously calibrated trim values for more accurate frequencies.
     pub fn use_irc(mut self) -> Self {
         self.clock_source = ClockSource::Internal;
         self
     }
 
+    /// Replaces the factory trim values of the internal reference clocks.
+    ///
+    /// `slow_irc` and `fast_irc` are the frequencies which result from the trim values, e.g., as
+    /// determined by `Mcg::auto_trim()`. They are used to calculate the reported clock
+    /// frequencies.
+    pub fn trim_irc<S, F>(mut self, trim: IrcTrim, slow_irc: S, fast_irc: F) -> Self
+    where
+        S: Into<Hertz>,
+        F: Into<Hertz>,
+    {
+        self.irc_trim = Some(trim);
+        self.slow_irc = slow_irc.into();
+        self.fast_irc = fast_irc.into();
+        self
+    }
+
     pub fn use_crystal<T: Into<Hertz>>(
         mut self,
         freq: T,
@@ -78,45 +144,270 @@ impl ClockConfiguration {
         self
     }
 
-    pub fn apply(self, sim: &mut SIM, osc: OSC0, mcg: MCG) -> Clocks {
-        // Use safe default frequencies if not specified.
+    /// Sets the additional load capacitance (in pF) for the crystal oscillator.
+    ///
+    /// The value is rounded down to a multiple of 2 pF, the maximum is 30 pF. The setting is only
+    /// used if a crystal has been selected with `use_crystal()`.
+    pub fn crystal_load_capacitance(mut self, pf: u8) -> Self {
+        self.crystal_load = pf.min(30) & !1;
+        self
+    }
+
+    /// Operates the crystal oscillator in high-gain instead of low-power mode.
+    pub fn crystal_high_gain(mut self) -> Self {
+        self.crystal_high_gain = true;
+        self
+    }
+
+    /// Enables the external clock monitor, which detects the loss of the external reference.
+    ///
+    /// The monitor is only active while the MCG is in a mode using the external reference and is
+    /// disabled automatically when switching to an internal mode.
+    pub fn enable_clock_monitor(mut self, action: LossAction) -> Self {
+        self.clock_monitor = Some(action);
+        self
+    }
+
+    /// Enables detection of a loss of lock of the PLL.
+    pub fn enable_loss_of_lock(mut self, action: LossAction) -> Self {
+        self.loss_of_lock = Some(action);
+        self
+    }
+
+    /// Enables MCGIRCLK and drives it from the slow internal reference clock (32 kHz).
+    pub fn enable_mcgirclk_slow(mut self) -> Self {
+        self.mcgirclk = Some(InternalReference::Slow);
+        self
+    }
+
+    /// Enables MCGIRCLK and drives it from the fast internal reference clock (4 MHz).
+    ///
+    /// The fast IRC is divided by `divider`, which is rounded down to a power of two between 1
+    /// and 128.
+    pub fn enable_mcgirclk_fast(mut self, divider: u8) -> Self {
+        let fcrdiv = 7 - u8::max(divider, 1).leading_zeros() as u8;
+        self.mcgirclk = Some(InternalReference::Fast(fcrdiv));
+        self
+    }
+
+    /// Enables OSCERCLK, optionally also in stop mode.
+    ///
+    /// OSCERCLK is only available if the system oscillator is used, i.e., if either a crystal or
+    /// an external clock has been selected.
+    pub fn enable_oscerclk(mut self, in_stop_mode: bool) -> Self {
+        self.oscerclk = Some(in_stop_mode);
+        self
+    }
+
+    /// Drives ERCLK32K from the system oscillator.
+    ///
+    /// The system oscillator only provides OSC32KCLK if a 32 kHz crystal or clock has been
+    /// selected as the external reference. Such a reference cannot be used for the PLL, so it
+    /// drives the FLL instead (FEE mode).
+    pub fn erclk32k_from_oscillator(mut self) -> Self {
+        self.erclk32k = Erclk32kSource::Oscillator;
+        self
+    }
+
+    /// Drives ERCLK32K from the RTC_CLKIN pin.
+    pub fn erclk32k_from_rtc_clkin<T: Into<Hertz>>(
+        mut self,
+        freq: T,
+        _rtc_clkin: gpioc::PC1<gpio::Input<gpio::Floating>>,
+    ) -> Self {
+        // As with the oscillator pins, the GPIO is dropped so that it is never reconfigured.
+        self.erclk32k = Erclk32kSource::RtcClkin(freq.into());
+        self
+    }
+
+    /// Checks whether the configuration can be applied without modifying any register.
+    pub fn check(&self) -> Result<(), ClockError> {
+        let dividers = self.dividers()?;
+        let settings = self.mcg_settings(dividers.drst_drs, dividers.pll);
+        settings.check_path(McgMode::FEI, settings.startup_mode())
+    }
+
+    /// Returns the frequency of ERCLK32K.
+    fn erclk32k_frequency(&self) -> Option<Hertz> {
+        match self.erclk32k {
+            // OSC32KCLK is only available in the low frequency range.
+            Erclk32kSource::Oscillator => match self.clock_source {
+                ClockSource::External(freq, _) if get_oscillator_range(freq) == 0 => Some(freq),
+                _ => None,
+            },
+            Erclk32kSource::RtcClkin(freq) => Some(freq),
+            Erclk32kSource::Lpo => Some(LPO.hz()),
+        }
+    }
+
+    /// Returns the MCG settings for the calculated dividers.
+    fn mcg_settings(&self, drst_drs: u8, pll: Option<PllDividers>) -> McgSettings {
+        let (oscclk, frdiv) = match self.clock_source {
+            ClockSource::Internal => (None, 0),
+            ClockSource::External(freq, mode) => {
+                let range = get_oscillator_range(freq);
+                (Some((freq.0, mode)), get_fll_reference_divider(freq, range))
+            }
+        };
+        McgSettings {
+            oscclk,
+            frdiv,
+            drst_drs,
+            slow_irc: self.slow_irc.0,
+            fast_irc: self.fast_irc.0,
+            irc: self.mcgirclk.unwrap_or(InternalReference::Slow),
+            pll,
+        }
+    }
+
+    /// Calculates all clock dividers without modifying any register.
+    fn dividers(&self) -> Result<ClockDividers, ClockError> {
+        // Use safe default frequencies if not specified. The bus clock follows the core clock
+        // up to its maximum.
         let core_clock = self.core_clock.unwrap_or(24_000_000.hz());
-        let bus_clock = self.core_clock.unwrap_or(24_000_000.hz());
+        let bus_clock = self
+            .bus_clock
+            .unwrap_or(u32::min(core_clock.0, MAX_BUS_CLOCK).hz());
+        check_clock_limits(core_clock, bus_clock)?;
 
-        match self.clock_source {
+        let (outdiv1, drst_drs, pll, actual_core_clock) = match self.clock_source {
             ClockSource::Internal => {
-                // ERCLK32K is provided by the LPO.
-                sim.sopt1.modify(|_, w| w.osc32ksel()._11());
+                let (outdiv1, drst_drs) = get_fll_dividers(core_clock)?;
+                let fll_clock = [640, 1280, 1920, 2560][drst_drs as usize] * self.slow_irc.0;
+                // A trimmed IRC can be faster than the nominal one, so the core clock may need a
+                // larger divider.
+                let (outdiv1, _) = get_output_dividers(fll_clock, outdiv1, 0);
+                (outdiv1, drst_drs, None, fll_clock / (outdiv1 as u32 + 1))
+            }
+            ClockSource::External(freq, _)
+                if get_pll_dividers(freq, MAX_CORE_CLOCK.hz()).is_some() =>
+            {
+                let pll =
+                    get_pll_dividers(freq, core_clock).ok_or(ClockError::CoreClockOutOfRange)?;
+                (pll.outdiv1, 0, Some(pll), pll.core_clock)
             }
-            ClockSource::External(_, _) => {
-                // ERCLK32K is provided by the system oscillator.
-                sim.sopt1.modify(|_, w| w.osc32ksel()._00());
+            ClockSource::External(freq, _) => {
+                // References which cannot be used for the PLL drive the FLL instead (FEE mode).
+                let range = get_oscillator_range(freq);
+                let frdiv = get_fll_reference_divider(freq, range);
+                let reference = freq.0 / get_fll_reference_divider_value(frdiv, range);
+                if !(31_250..=39_062).contains(&reference) {
+                    return Err(ClockError::ReferenceOutOfRange);
+                }
+                let (outdiv1, drst_drs) = get_fee_dividers(reference, core_clock)
+                    .ok_or(ClockError::CoreClockOutOfRange)?;
+                let factor = [640, 1280, 1920, 2560][drst_drs as usize];
+                // The dividers are set in FEI mode, so they also have to be valid for the IRC.
+                let (outdiv1, _) =
+                    get_output_dividers(factor * u32::max(reference, self.slow_irc.0), outdiv1, 0);
+                (
+                    outdiv1,
+                    drst_drs,
+                    None,
+                    factor * reference / (outdiv1 as u32 + 1),
+                )
             }
+        };
+        // Without a requested bus clock, run the bus at the achieved core clock if possible.
+        let bus_clock = self
+            .bus_clock
+            .unwrap_or(u32::min(actual_core_clock, MAX_BUS_CLOCK).hz());
+        let outdiv4 = get_bus_divider(actual_core_clock, bus_clock.0)?;
+        let actual_bus_clock = actual_core_clock / (outdiv4 as u32 + 1);
+        if actual_core_clock > MAX_CORE_CLOCK || actual_bus_clock > MAX_BUS_CLOCK {
+            return Err(ClockError::CoreClockOutOfRange);
         }
 
+        Ok(ClockDividers {
+            outdiv1,
+            outdiv4,
+            drst_drs,
+            pll,
+        })
+    }
+
+    /// Applies the configuration.
+    ///
+    /// The requested frequencies are checked before any register is modified. If they cannot be
+    /// achieved, the clock configuration is left unchanged and the error is returned together
+    /// with OSC0 and MCG, so that another configuration can be applied.
+    pub fn apply(
+        self,
+        sim: &mut SIM,
+        osc: OSC0,
+        mcg: MCG,
+    ) -> Result<Clocks, (ClockError, OSC0, MCG)> {
+        self.freeze(sim, osc, mcg).map(|(clocks, _)| clocks)
+    }
+
+    /// Applies the configuration and returns an MCG driver which can be used to switch between
+    /// clock modes at runtime.
+    ///
+    /// The MCG is in PEE mode afterwards if an external reference was selected, in FEE mode if
+    /// the external reference cannot be used for the PLL, and in FEI mode otherwise. Errors are
+    /// returned as for [`apply`](Self::apply).
+    pub fn freeze(
+        self,
+        sim: &mut SIM,
+        osc: OSC0,
+        mcg: MCG,
+    ) -> Result<(Clocks, Mcg), (ClockError, OSC0, MCG)> {
+        // Calculate all dividers before any register is modified.
+        let ClockDividers {
+            outdiv1,
+            outdiv4,
+            drst_drs,
+            pll,
+        } = match self.dividers() {
+            Ok(dividers) => dividers,
+            Err(err) => return Err((err, osc, mcg)),
+        };
+        // Also check all modes on the way to the target mode, so that the mode switches below
+        // cannot fail after registers have been modified.
+        let settings = self.mcg_settings(drst_drs, pll);
+        let target = settings.startup_mode();
+        if let Err(err) = settings.check_path(McgMode::FEI, target) {
+            return Err((err, osc, mcg));
+        }
+
+        let oscclk = match self.clock_source {
+            ClockSource::Internal => None,
+            ClockSource::External(freq, _) => Some(freq),
+        };
+
+        match self.erclk32k {
+            Erclk32kSource::Oscillator => sim.sopt1.modify(|_, w| w.osc32ksel()._00()),
+            Erclk32kSource::RtcClkin(_) => sim.sopt1.modify(|_, w| w.osc32ksel()._10()),
+            Erclk32kSource::Lpo => sim.sopt1.modify(|_, w| w.osc32ksel()._11()),
+        }
+        let erclk32k = self.erclk32k_frequency();
+
         // Always provide the COP (watchdog) with the LPO clock.
         sim.copc.write(|w| w.copclks().clear_bit());
 
-        // If we use the internal oscillator, the PLL is not in use, so use the FLL output for
-        // various peripherals instead.
-        match self.clock_source {
-            ClockSource::Internal => {
+        // If the PLL is not in use, use the FLL output for various peripherals instead.
+        let pllfllsel = match pll {
+            None => {
                 // MCGFLLCLK
                 sim.sopt2.modify(|_, w| w.pllfllsel().clear_bit());
+                false
             }
-            ClockSource::External(_, _) => {
+            Some(_) => {
                 // MCGPLLCLK / 2
                 sim.sopt2.modify(|_, w| w.pllfllsel().set_bit());
+                true
             }
-        }
+        };
         // Use the PLL or FLL for the TPM counter clock.
         sim.sopt2.modify(|_, w| w.tpmsrc()._01());
 
+        let oscerclk_stop = self.oscerclk.unwrap_or(false);
+        let oscerclk = self.oscerclk.and(oscclk);
         osc.cr.write(|w| {
             w.erclken()
-                .clear_bit() // Disable the external reference clock.
+                .bit(oscerclk.is_some()) // Enable the external reference clock if requested.
                 .erefsten()
-                .clear_bit() // Disable external reference clock in stop mode.
+                .bit(oscerclk.is_some() && oscerclk_stop) // Keep it running in stop mode.
                 .sc2p()
                 .clear_bit() // Clear any capacitor load.
                 .sc4p()
@@ -127,61 +418,933 @@ impl ClockConfiguration {
                 .clear_bit()
         });
 
-        // We assume that we start in FEI mode coming from a reset.
+        if let Some(trim) = self.irc_trim {
+            mcg.c3.write(|w| unsafe { w.sctrim().bits(trim.sctrim) });
+            mcg.c4.modify(|_, w| {
+                unsafe { w.fctrim().bits(trim.fctrim) }
+                    .scftrim()
+                    .bit(trim.scftrim)
+            });
+        }
 
-        match self.clock_source {
-            ClockSource::Internal => {
-                // We want to select a high FLL frequency if the user requested a high core
-                // frequency or if lower frequencies are not close to a multiple of it.
-                let (outdiv1, range) = match core_clock.0 {
-                    0..5800000 => {
-                        let outdiv1 = ((640 * 32767) + core_clock.0 / 2) / core_clock.0;
-                        (outdiv1 - 1, 0)
-                    }
-                    5800000..6400000 => (6, 1),   // 5991862 Hz
-                    6400000..6700000 => (10, 2),  // 6291456 Hz
-                    6700000..7500000 => (2, 0),   // 6990506 Hz
-                    7500000..8000000 => (7, 2),   // 7864320 Hz
-                    8000000..8700000 => (4, 1),   // 8388608 Hz
-                    8700000..9800000 => (6, 2),   // 8987794 Hz
-                    9800000..11600000 => (1, 0),  // 10485760 Hz
-                    11600000..13300000 => (4, 2), // 12582912 Hz
-                    13300000..14900000 => (2, 1), // 15728640 Hz
-                    14900000..18400000 => (3, 2), // 15728640 Hz
-                    18400000..26300000 => (0, 0), // 20971520 Hz
-                    26300000..36800000 => (1, 2), // 31457280 Hz
-                    _ => (0, 1),                  // 41943040 Hz
-                };
-
-                let actual_core_clock = [640, 1280, 1920, 2560][range] * 32768 / (outdiv1 + 1);
-                // TODO: Use actual core clock.
-                let outdiv4 = ((actual_core_clock + bus_clock.0 - 1) / bus_clock.0).clamp(1, 8) - 1;
-                let actual_bus_clock = actual_core_clock / (outdiv4 + 1);
-
-                // We stay in FEI mode, so we just update the clock dividers and the FLL range.
-
-                sim.clkdiv1.modify(|_, w| {
-                    w.outdiv1()
-                        .bits(outdiv1 as u8)
-                        .outdiv4()
-                        .bits(outdiv4 as u8)
+        // Configure MCGIRCLK. The fast IRC divider must not be changed while the fast IRC is
+        // enabled, so the divider is set before the fast IRC is selected.
+        let mcgirclk = match self.mcgirclk {
+            Some(InternalReference::Slow) => {
+                mcg.c2.modify(|_, w| w.ircs().clear_bit());
+                mcg.c1.modify(|_, w| w.irclken().set_bit());
+                while mcg.s.read().ircst().bit_is_set() {
+                    // Wait until the slow IRC is selected.
+                }
+                Some(self.slow_irc)
+            }
+            Some(InternalReference::Fast(fcrdiv)) => {
+                mcg.c2.modify(|_, w| w.ircs().clear_bit());
+                while mcg.s.read().ircst().bit_is_set() {
+                    // Wait until the fast IRC is deselected before changing its divider.
+                }
+                // LOCS0 and ATMF are cleared by writing 1, so they have to be written as 0 here.
+                mcg.sc.modify(|_, w| {
+                    w.fcrdiv()
+                        .bits(fcrdiv)
+                        .locs0()
+                        .clear_bit()
+                        .atmf()
+                        .clear_bit()
+                });
+                mcg.c2.modify(|_, w| w.ircs().set_bit());
+                mcg.c1.modify(|_, w| w.irclken().set_bit());
+                while mcg.s.read().ircst().bit_is_clear() {
+                    // Wait until the fast IRC is selected.
+                }
+                Some((self.fast_irc.0 >> fcrdiv).hz())
+            }
+            None => {
+                mcg.c1.modify(|_, w| w.irclken().clear_bit());
+                None
+            }
+        };
+
+        // We assume that we start in FEI mode coming from a reset or from `Mcg::free()`.
+
+        if let ClockSource::External(freq, mode) = self.clock_source {
+            let range = get_oscillator_range(freq);
+
+            // Configure the load capacitors of the crystal oscillator. An external clock
+            // input keeps the capacitors disabled.
+            if mode == ExternalMode::Crystal {
+                let load = self.crystal_load;
+                osc.cr.modify(|_, w| {
+                    w.sc2p()
+                        .bit(load & 2 != 0)
+                        .sc4p()
+                        .bit(load & 4 != 0)
+                        .sc8p()
+                        .bit(load & 8 != 0)
+                        .sc16p()
+                        .bit(load & 16 != 0)
                 });
-                mcg.c4.modify(|_, w| w.drst_drs().bits(range as u8));
+            }
+            mcg.c2.modify(|_, w| {
+                unsafe { w.range0().bits(range) }
+                    .hgo0()
+                    .bit(self.crystal_high_gain)
+                    .erefs0()
+                    .bit(mode == ExternalMode::Crystal)
+            });
+        }
+
+        // The dividers are also valid for all modes on the way to the target mode, so they are set
+        // before the first switch.
+        sim.clkdiv1
+            .modify(|_, w| w.outdiv1().bits(outdiv1).outdiv4().bits(outdiv4));
+        mcg.c4
+            .modify(|_, w| w.dmx32().clear_bit().drst_drs().bits(drst_drs));
+
+        let mut mcg = Mcg {
+            mcg,
+            osc,
+            mode: McgMode::FEI,
+            settings,
+            outdiv1,
+            outdiv4,
+            clock_monitor: self.clock_monitor,
+            clocks: Clocks {
+                coreclk: 0.hz(),
+                busclk: 0.hz(),
+                mcgirclk,
+                oscerclk,
+                erclk32k,
+                mcgfllclk: None,
+                mcgpllclk_div2: None,
+                pllfllsel,
+            },
+        };
+        mcg.update_clocks();
+
+        // Walk FEI -> FBE -> PBE -> PEE or FEI -> FEE with the regular mode transitions. The
+        // path has been checked above, so the switch cannot fail.
+        let _ = mcg.switch_to(target, sim);
+
+        let loss_of_lock = self.loss_of_lock;
+        mcg.mcg
+            .c6
+            .modify(|_, w| w.lolie0().bit(loss_of_lock == Some(LossAction::Interrupt)));
+        mcg.mcg
+            .c8
+            .modify(|_, w| w.lolre().bit(loss_of_lock == Some(LossAction::Reset)));
+        mcg.configure_clock_monitor();
+
+        Ok((mcg.clocks, mcg))
+    }
+}
+
+/// Action taken by the MCG when the external reference or the PLL lock is lost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LossAction {
+    /// Generate an MCG interrupt.
+    Interrupt,
+    /// Reset the chip.
+    Reset,
+}
+
+/// Loss of clock and loss of lock status flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct McgStatus {
+    /// The external reference clock was lost.
+    pub loss_of_clock: bool,
+    /// The PLL lost its lock.
+    pub loss_of_lock: bool,
+}
+
+impl McgStatus {
+    /// Returns whether the last reset was caused by a loss of clock or loss of lock.
+    ///
+    /// The MCG status flags are cleared by the reset, so the reset controller has to be queried
+    /// instead.
+    pub fn last_reset(rcm: &RCM) -> McgStatus {
+        let srs0 = rcm.srs0.read();
+        McgStatus {
+            loss_of_clock: srs0.loc().bit_is_set(),
+            loss_of_lock: srs0.lol().bit_is_set(),
+        }
+    }
+}
+
+/// Operating modes of the multipurpose clock generator (MCG).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum McgMode {
+    /// FLL engaged internal.
+    FEI,
+    /// FLL engaged external.
+    FEE,
+    /// FLL bypassed internal.
+    FBI,
+    /// FLL bypassed external.
+    FBE,
+    /// PLL bypassed external.
+    PBE,
+    /// PLL engaged external.
+    PEE,
+    /// Bypassed low power internal.
+    BLPI,
+    /// Bypassed low power external.
+    BLPE,
+}
+
+impl McgMode {
+    fn to_index(self) -> usize {
+        match self {
+            McgMode::FEI => 0,
+            McgMode::FEE => 1,
+            McgMode::FBI => 2,
+            McgMode::FBE => 3,
+            McgMode::PBE => 4,
+            McgMode::PEE => 5,
+            McgMode::BLPI => 6,
+            McgMode::BLPE => 7,
+        }
+    }
+
+    /// Returns the modes which can be entered directly from this mode.
+    fn neighbours(self) -> &'static [McgMode] {
+        use McgMode::*;
+        match self {
+            FEI => &[FEE, FBI, FBE],
+            FEE => &[FEI, FBI, FBE],
+            FBI => &[FEI, FEE, FBE, BLPI],
+            FBE => &[FEI, FEE, FBI, PBE, BLPE],
+            PBE => &[FBE, PEE, BLPE],
+            PEE => &[PBE],
+            BLPI => &[FBI],
+            BLPE => &[FBE, PBE],
+        }
+    }
+
+    fn is_external(self) -> bool {
+        use McgMode::*;
+        match self {
+            FEI | FBI | BLPI => false,
+            FEE | FBE | PBE | PEE | BLPE => true,
+        }
+    }
+
+    fn uses_pll(self) -> bool {
+        self == McgMode::PBE || self == McgMode::PEE
+    }
+
+    fn uses_fll(self) -> bool {
+        use McgMode::*;
+        match self {
+            FEI | FEE | FBI | FBE => true,
+            PBE | PEE | BLPI | BLPE => false,
+        }
+    }
+}
+
+/// Returns whether the reference manual allows switching directly between the two modes.
+fn is_legal_transition(from: McgMode, to: McgMode) -> bool {
+    from.neighbours().contains(&to)
+}
+
+/// Returns the next mode on the shortest legal path from `from` to `to`.
+fn next_mode(from: McgMode, to: McgMode) -> McgMode {
+    // Breadth-first search starting at the target, the mode graph is undirected.
+    let mut distance = [usize::MAX; 8];
+    let mut queue = [to; 8];
+    let (mut head, mut tail) = (0, 1);
+    distance[to.to_index()] = 0;
+    while head < tail {
+        let mode = queue[head];
+        head += 1;
+        for &neighbour in mode.neighbours() {
+            if distance[neighbour.to_index()] == usize::MAX {
+                distance[neighbour.to_index()] = distance[mode.to_index()] + 1;
+                queue[tail] = neighbour;
+                tail += 1;
+            }
+        }
+    }
+    from.neighbours()
+        .iter()
+        .cloned()
+        .min_by_key(|mode| distance[mode.to_index()])
+        .unwrap_or(to)
+}
+
+/// Error returned when the clock configuration cannot be applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockError {
+    /// The requested core clock is above the maximum of 48 MHz.
+    CoreClockTooHigh,
+    /// The requested bus clock is above the maximum of 24 MHz.
+    BusClockTooHigh,
+    /// The requested bus clock is above the requested core clock.
+    BusClockAboveCoreClock,
+    /// The requested core clock cannot be generated from the FLL or PLL output.
+    CoreClockOutOfRange,
+    /// The requested bus clock cannot be generated by dividing the core clock by 1 to 8.
+    BusClockOutOfRange,
+    /// The MCG cannot switch directly between the two modes.
+    IllegalTransition(McgMode, McgMode),
+    /// The mode requires an external reference clock, but none was configured.
+    NoExternalReference,
+    /// The mode requires the PLL, but the external reference cannot be used for the PLL.
+    NoPll,
+    /// The external reference can be used neither for the PLL nor for the FLL.
+    ReferenceOutOfRange,
+    /// The internal reference clock cannot be trimmed while it is used by the MCG.
+    IrcInUse,
+    /// The requested internal reference clock frequency is outside of the trimmable range.
+    TrimOutOfRange,
+    /// The bus clock is outside of the 8-16 MHz range required for automatic trimming.
+    TrimReferenceOutOfRange,
+    /// The automatic trim machine failed.
+    TrimFailed,
+    /// The selected clock is not enabled.
+    ClockDisabled,
+}
+
+/// Internal reference clock selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Irc {
+    /// Slow internal reference clock (32 kHz).
+    Slow,
+    /// Fast internal reference clock (4 MHz).
+    Fast,
+}
+
+/// Trim values of the internal reference clocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IrcTrim {
+    /// Slow IRC trim (MCG_C3[SCTRIM]).
+    pub sctrim: u8,
+    /// Slow IRC fine trim (MCG_C4[SCFTRIM]).
+    pub scftrim: bool,
+    /// Fast IRC trim (MCG_C4[FCTRIM]), 4 bits.
+    pub fctrim: u8,
+}
+
+/// Returns the auto trim compare value (MCG_ATCV) for the target IRC frequency.
+///
+/// The ATM counts bus clock cycles during 21 periods of the slow IRC or during 21 * 128 periods
+/// of the fast IRC.
+fn get_auto_trim_compare_value(busclk: u32, irc: Irc, target: u32) -> Result<u16, ClockError> {
+    if !(8_000_000..=16_000_000).contains(&busclk) {
+        return Err(ClockError::TrimReferenceOutOfRange);
+    }
+    let (range, periods) = match irc {
+        Irc::Slow => (31_250..=39_062, 21),
+        Irc::Fast => (3_000_000..=5_000_000, 21 * 128),
+    };
+    if !range.contains(&target) {
+        return Err(ClockError::TrimOutOfRange);
+    }
+    // The result always fits into 16 bits for the allowed frequency ranges.
+    let atcv = (busclk as u64 * periods + target as u64 / 2) / target as u64;
+    Ok(atcv as u16)
+}
+
+/// Static MCG settings which determine the output frequency in each mode.
+#[derive(Clone, Copy)]
+struct McgSettings {
+    /// Frequency of the external reference, if any.
+    oscclk: Option<(u32, ExternalMode)>,
+    /// FLL external reference divider (MCG_C1[FRDIV]).
+    frdiv: u8,
+    /// FLL range (MCG_C4[DRST_DRS]).
+    drst_drs: u8,
+    /// Frequency of the slow internal reference clock.
+    slow_irc: u32,
+    /// Frequency of the fast internal reference clock before division by MCG_SC[FCRDIV].
+    fast_irc: u32,
+    /// Internal reference clock selected by MCG_C2[IRCS].
+    irc: InternalReference,
+    /// PLL settings, if the external reference can be used for the PLL.
+    pll: Option<PllDividers>,
+}
+
+impl McgSettings {
+    fn check(&self, mode: McgMode) -> Result<(), ClockError> {
+        if mode.is_external() && self.oscclk.is_none() {
+            Err(ClockError::NoExternalReference)
+        } else if mode.uses_pll() && self.pll.is_none() {
+            Err(ClockError::NoPll)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the mode entered by `ClockConfiguration::apply()`.
+    fn startup_mode(&self) -> McgMode {
+        match (self.oscclk, self.pll) {
+            (None, _) => McgMode::FEI,
+            (Some(_), None) => McgMode::FEE,
+            (Some(_), Some(_)) => McgMode::PEE,
+        }
+    }
+
+    /// Checks all modes on the shortest legal path from `from` to `to`.
+    fn check_path(&self, from: McgMode, to: McgMode) -> Result<(), ClockError> {
+        let mut current = from;
+        while current != to {
+            current = next_mode(current, to);
+            self.check(current)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the frequency of the selected internal reference clock.
+    fn irc_clock(&self) -> u32 {
+        match self.irc {
+            InternalReference::Slow => self.slow_irc,
+            InternalReference::Fast(fcrdiv) => self.fast_irc >> fcrdiv,
+        }
+    }
+
+    /// Returns the FLL output frequency in the mode, if the FLL is enabled.
+    fn fll_clock(&self, mode: McgMode) -> Option<u32> {
+        if !mode.uses_fll() {
+            return None;
+        }
+        let reference = match mode {
+            McgMode::FEI | McgMode::FBI => self.slow_irc,
+            _ => {
+                let oscclk = self.oscclk?.0;
+                let range = get_oscillator_range(oscclk.hz());
+                oscclk / get_fll_reference_divider_value(self.frdiv, range)
+            }
+        };
+        Some(reference * [640, 1280, 1920, 2560][self.drst_drs as usize])
+    }
+
+    /// Returns the PLL output frequency in the mode, if the PLL is enabled.
+    fn pll_clock(&self, mode: McgMode) -> Option<u32> {
+        if mode.uses_pll() {
+            self.pll.map(|pll| pll.pll_clock)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the frequency of MCGOUTCLK in the mode.
+    fn mcgoutclk(&self, mode: McgMode) -> Result<u32, ClockError> {
+        self.check(mode)?;
+        Ok(match mode {
+            McgMode::FEI | McgMode::FEE => self.fll_clock(mode).unwrap(),
+            McgMode::FBI | McgMode::BLPI => self.irc_clock(),
+            McgMode::FBE | McgMode::PBE | McgMode::BLPE => self.oscclk.unwrap().0,
+            McgMode::PEE => self.pll_clock(mode).unwrap(),
+        })
+    }
+}
+
+/// Returns the core and bus clock dividers for the new MCGOUTCLK frequency.
+///
+/// The current dividers are kept unless they would result in a core or bus clock above the
+/// supported maximum, in which case they are increased.
+fn get_output_dividers(mcgoutclk: u32, outdiv1: u8, outdiv4: u8) -> (u8, u8) {
+    let min_outdiv1 = (mcgoutclk.div_ceil(MAX_CORE_CLOCK).max(1) - 1) as u8;
+    let outdiv1 = u8::min(u8::max(outdiv1, min_outdiv1), 15);
+    let core_clock = mcgoutclk / (outdiv1 as u32 + 1);
+    let min_outdiv4 = (core_clock.div_ceil(MAX_BUS_CLOCK).max(1) - 1) as u8;
+    let outdiv4 = u8::min(u8::max(outdiv4, min_outdiv4), 7);
+    (outdiv1, outdiv4)
+}
+
+/// Returns the core and bus clock dividers for FEI mode with the reset FLL range.
+///
+/// The reset dividers are used unless a trimmed slow IRC would result in a core or bus clock above
+/// the supported maximum.
+fn get_reset_dividers(slow_irc: u32) -> (u8, u8) {
+    get_output_dividers(640 * slow_irc, RESET_OUTDIV1, RESET_OUTDIV4)
+}
+
+/// MCG driver which keeps track of the current clock mode.
+///
+/// Every mode switch changes the clock frequencies, so the `Clocks` returned by the switch
+/// replace all previous values. Peripherals which were configured with the old `Clocks` have to
+/// be released and configured again.
+pub struct Mcg {
+    mcg: MCG,
+    osc: OSC0,
+    mode: McgMode,
+    settings: McgSettings,
+    outdiv1: u8,
+    outdiv4: u8,
+    clock_monitor: Option<LossAction>,
+    clocks: Clocks,
+}
+
+impl Mcg {
+    /// Returns the current clock mode.
+    pub fn mode(&self) -> McgMode {
+        self.mode
+    }
+
+    /// Returns the clock frequencies in the current mode.
+    pub fn clocks(&self) -> Clocks {
+        self.clocks
+    }
+
+    /// Switches to a mode which can be entered directly from the current mode.
+    pub fn transition(&mut self, mode: McgMode, sim: &mut SIM) -> Result<Clocks, ClockError> {
+        if mode == self.mode {
+            return Ok(self.clocks);
+        }
+        if !is_legal_transition(self.mode, mode) {
+            return Err(ClockError::IllegalTransition(self.mode, mode));
+        }
+        let mcgoutclk = self.settings.mcgoutclk(mode)?;
+
+        // The dividers are only ever increased, so they have to be set before the switch.
+        let (outdiv1, outdiv4) = get_output_dividers(mcgoutclk, self.outdiv1, self.outdiv4);
+        if (outdiv1, outdiv4) != (self.outdiv1, self.outdiv4) {
+            sim.clkdiv1
+                .modify(|_, w| w.outdiv1().bits(outdiv1).outdiv4().bits(outdiv4));
+            self.outdiv1 = outdiv1;
+            self.outdiv4 = outdiv4;
+        }
+
+        // The clock monitor must be disabled before switching to an internal mode.
+        if !mode.is_external() {
+            self.mcg.c6.modify(|_, w| w.cme0().clear_bit());
+        }
+        self.enter(mode);
+        self.mode = mode;
+        self.configure_clock_monitor();
+        self.update_clocks();
+        Ok(self.clocks)
+    }
+
+    /// Switches to any mode, passing through the intermediate modes required by the reference
+    /// manual.
+    ///
+    /// All modes on the way are checked before the first switch, so the mode is left unchanged
+    /// if an error is returned.
+    pub fn switch_to(&mut self, mode: McgMode, sim: &mut SIM) -> Result<Clocks, ClockError> {
+        self.settings.check_path(self.mode, mode)?;
+        while self.mode != mode {
+            self.transition(next_mode(self.mode, mode), sim)?;
+        }
+        Ok(self.clocks)
+    }
 
-                Clocks {
-                    coreclk: actual_core_clock.hz(),
-                    busclk: actual_bus_clock.hz(),
+    /// Returns the loss of clock and loss of lock status flags.
+    pub fn status(&self) -> McgStatus {
+        McgStatus {
+            loss_of_clock: self.mcg.sc.read().locs0().bit_is_set(),
+            loss_of_lock: self.mcg.s.read().lols0().bit_is_set(),
+        }
+    }
+
+    /// Clears the loss of clock and loss of lock status flags.
+    pub fn clear_status(&mut self) {
+        // Both flags are cleared by writing 1, ATMF has to be written as 0 to not clear it.
+        self.mcg
+            .sc
+            .modify(|_, w| w.locs0().set_bit().atmf().clear_bit());
+        self.mcg.s.write(|w| w.lols0().set_bit());
+    }
+
+    /// Falls back to the internal reference clock (FEI mode) after the external reference or the
+    /// PLL lock has been lost.
+    ///
+    /// The regular mode transitions would wait for the lost clock, so the MCG is forced into FBI
+    /// mode directly. The status flags are cleared and the degraded clock frequencies are
+    /// returned. Modes using the external reference should only be entered again once the
+    /// external clock has been restored.
+    pub fn recover_from_clock_loss(&mut self, sim: &mut SIM) -> Clocks {
+        self.mcg.c6.modify(|_, w| w.cme0().clear_bit());
+        self.clear_status();
+
+        // FEI mode never fails, so the dividers are chosen for FEI.
+        let mcgoutclk = self.settings.mcgoutclk(McgMode::FEI).unwrap_or(0);
+        let (outdiv1, outdiv4) = get_output_dividers(mcgoutclk, self.outdiv1, self.outdiv4);
+        sim.clkdiv1
+            .modify(|_, w| w.outdiv1().bits(outdiv1).outdiv4().bits(outdiv4));
+        self.outdiv1 = outdiv1;
+        self.outdiv4 = outdiv4;
+
+        let mcg = &self.mcg;
+        mcg.c2.modify(|_, w| w.lp().clear_bit());
+        mcg.c1.modify(|_, w| w.clks()._01().irefs().set_bit());
+        mcg.c6.modify(|_, w| w.plls().clear_bit());
+        while mcg.s.read().clkst().bits() != 0x1 {
+            // Wait until the internal reference is selected.
+        }
+        self.mode = McgMode::FBI;
+        self.enter(McgMode::FEI);
+        self.mode = McgMode::FEI;
+        self.update_clocks();
+        self.clocks
+    }
+
+    /// Returns the current trim values of the internal reference clocks.
+    ///
+    /// The values can be stored and passed to `ClockConfiguration::trim_irc()` after the next
+    /// reset.
+    pub fn irc_trim(&self) -> IrcTrim {
+        let c4 = self.mcg.c4.read();
+        IrcTrim {
+            sctrim: self.mcg.c3.read().sctrim().bits(),
+            scftrim: c4.scftrim().bit(),
+            fctrim: c4.fctrim().bits(),
+        }
+    }
+
+    /// Trims an internal reference clock to the target frequency using the automatic trim
+    /// machine (ATM).
+    ///
+    /// The bus clock, which has to be between 8 and 16 MHz, is used as the reference, so the MCG
+    /// has to be in a mode using the external reference. The returned clock frequencies reflect
+    /// the trimmed frequency.
+    pub fn auto_trim<T: Into<Hertz>>(&mut self, irc: Irc, target: T) -> Result<Clocks, ClockError> {
+        let target = target.into().0;
+        if !self.mode.is_external() {
+            return Err(ClockError::IrcInUse);
+        }
+        let atcv = get_auto_trim_compare_value(self.clocks.busclk.0, irc, target)?;
+
+        let mcg = &self.mcg;
+        mcg.atcvh
+            .write(|w| unsafe { w.atcvh().bits((atcv >> 8) as u8) });
+        mcg.atcvl.write(|w| unsafe { w.atcvl().bits(atcv as u8) });
+        // ATMF and LOCS0 are cleared by writing 1, so LOCS0 is written as 0 to preserve it.
+        mcg.sc.modify(|_, w| {
+            w.atms()
+                .bit(irc == Irc::Fast)
+                .atmf()
+                .set_bit()
+                .locs0()
+                .clear_bit()
+        });
+        mcg.sc
+            .modify(|_, w| w.atme().set_bit().atmf().clear_bit().locs0().clear_bit());
+        while mcg.sc.read().atme().bit_is_set() {
+            // Wait until the ATM has finished.
+        }
+        if mcg.sc.read().atmf().bit_is_set() {
+            mcg.sc.modify(|_, w| w.atmf().set_bit().locs0().clear_bit());
+            return Err(ClockError::TrimFailed);
+        }
+
+        match irc {
+            Irc::Slow => self.settings.slow_irc = target,
+            Irc::Fast => self.settings.fast_irc = target,
+        }
+        self.update_clocks();
+        Ok(self.clocks)
+    }
+
+    /// Returns to FEI mode with the reset dividers and releases the MCG and OSC0 peripherals.
+    ///
+    /// `ClockConfiguration::apply()` expects the MCG in its reset state, so the peripherals can
+    /// be passed to it again to apply a different configuration.
+    pub fn free(mut self, sim: &mut SIM) -> (MCG, OSC0) {
+        // Neither a loss of clock nor a loss of lock must cause a reset or an interrupt while the
+        // external reference is disabled.
+        self.mcg
+            .c6
+            .modify(|_, w| w.cme0().clear_bit().lolie0().clear_bit());
+        self.mcg.c8.modify(|_, w| w.lolre().clear_bit());
+        // FEI mode is reachable from every mode the settings allowed to enter.
+        let _ = self.switch_to(McgMode::FEI, sim);
+
+        // Lower the FLL output before the dividers are reset to not exceed the core clock limit.
+        self.mcg
+            .c4
+            .modify(|_, w| w.dmx32().clear_bit().drst_drs().bits(0));
+        let (outdiv1, outdiv4) = get_reset_dividers(self.settings.slow_irc);
+        sim.clkdiv1
+            .modify(|_, w| w.outdiv1().bits(outdiv1).outdiv4().bits(outdiv4));
+        (self.mcg, self.osc)
+    }
+
+    fn enter(&mut self, mode: McgMode) {
+        let mcg = &self.mcg;
+        let frdiv = self.settings.frdiv;
+        match mode {
+            McgMode::FEI => {
+                mcg.c1.modify(|_, w| w.clks()._00().irefs().set_bit());
+                while mcg.s.read().irefst().bit_is_clear() {
+                    // Wait until the FLL uses the internal reference.
+                }
+                while mcg.s.read().clkst().bits() != 0x0 {
+                    // Wait until the FLL output is selected.
                 }
-                /*Clocks {
-                    coreclk: core_clock,
-                    busclk: bus_clock,
-                }*/
             }
-            ClockSource::External(freq, mode) => {
-                panic!("Not yet implemented.");
+            McgMode::FEE => {
+                mcg.c1
+                    .modify(|_, w| w.clks()._00().frdiv().bits(frdiv).irefs().clear_bit());
+                self.wait_for_oscillator();
+                while mcg.s.read().irefst().bit_is_set() {
+                    // Wait until the FLL uses the external reference.
+                }
+                while mcg.s.read().clkst().bits() != 0x0 {
+                    // Wait until the FLL output is selected.
+                }
+            }
+            McgMode::FBI => {
+                mcg.c2.modify(|_, w| w.lp().clear_bit());
+                mcg.c1.modify(|_, w| w.clks()._01().irefs().set_bit());
+                while mcg.s.read().irefst().bit_is_clear() {
+                    // Wait until the FLL uses the internal reference.
+                }
+                while mcg.s.read().clkst().bits() != 0x1 {
+                    // Wait until the internal reference is selected.
+                }
+            }
+            McgMode::FBE => {
+                mcg.c2.modify(|_, w| w.lp().clear_bit());
+                mcg.c6.modify(|_, w| w.plls().clear_bit());
+                while mcg.s.read().pllst().bit_is_set() {
+                    // Wait until the FLL is selected.
+                }
+                mcg.c1
+                    .modify(|_, w| w.clks()._10().frdiv().bits(frdiv).irefs().clear_bit());
+                self.wait_for_oscillator();
+                while mcg.s.read().irefst().bit_is_set() {
+                    // Wait until the FLL uses the external reference.
+                }
+                while mcg.s.read().clkst().bits() != 0x2 {
+                    // Wait until the external reference is selected.
+                }
+            }
+            McgMode::PBE => {
+                if self.mode == McgMode::PEE {
+                    mcg.c1.modify(|_, w| w.clks()._10());
+                } else {
+                    // The PLL is disabled in FBE and BLPE mode, so it can be reconfigured.
+                    let pll = self.settings.pll.unwrap();
+                    mcg.c5.write(|w| unsafe { w.prdiv0().bits(pll.prdiv0) });
+                    mcg.c6
+                        .modify(|_, w| unsafe { w.vdiv0().bits(pll.vdiv0) }.plls().set_bit());
+                    mcg.c2.modify(|_, w| w.lp().clear_bit());
+                    while mcg.s.read().pllst().bit_is_clear() {
+                        // Wait until the PLL is selected.
+                    }
+                    while mcg.s.read().lock0().bit_is_clear() {
+                        // Wait until the PLL is locked.
+                    }
+                }
+                while mcg.s.read().clkst().bits() != 0x2 {
+                    // Wait until the external reference is selected.
+                }
+            }
+            McgMode::PEE => {
+                mcg.c1.modify(|_, w| w.clks()._00());
+                while mcg.s.read().clkst().bits() != 0x3 {
+                    // Wait until the PLL output is selected.
+                }
+            }
+            McgMode::BLPI | McgMode::BLPE => {
+                // The FLL and the PLL are disabled in low power mode.
+                mcg.c2.modify(|_, w| w.lp().set_bit());
             }
         }
     }
+
+    fn configure_clock_monitor(&mut self) {
+        if let Some(action) = self.clock_monitor {
+            let enable = self.mode.is_external();
+            self.mcg
+                .c2
+                .modify(|_, w| w.locre0().bit(action == LossAction::Reset));
+            self.mcg.c6.modify(|_, w| w.cme0().bit(enable));
+        }
+    }
+
+    fn wait_for_oscillator(&self) {
+        if let Some((_, ExternalMode::Crystal)) = self.settings.oscclk {
+            while self.mcg.s.read().oscinit0().bit_is_clear() {
+                // Wait until the crystal oscillator is stable.
+            }
+        }
+    }
+
+    fn update_clocks(&mut self) {
+        // The mode has been checked before, so the MCG output is always available.
+        let mcgoutclk = self.settings.mcgoutclk(self.mode).unwrap_or(0);
+        let coreclk = mcgoutclk / (self.outdiv1 as u32 + 1);
+        let busclk = coreclk / (self.outdiv4 as u32 + 1);
+        self.clocks.coreclk = coreclk.hz();
+        self.clocks.busclk = busclk.hz();
+        self.clocks.mcgfllclk = self.settings.fll_clock(self.mode).map(|freq| freq.hz());
+        if self.clocks.mcgirclk.is_some() {
+            self.clocks.mcgirclk = Some(self.settings.irc_clock().hz());
+        }
+        self.clocks.mcgpllclk_div2 = self
+            .settings
+            .pll_clock(self.mode)
+            .map(|freq| (freq / 2).hz());
+    }
+}
+
+/// Clock divider settings calculated from a `ClockConfiguration`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ClockDividers {
+    /// Core clock divider (SIM_CLKDIV1[OUTDIV1]).
+    outdiv1: u8,
+    /// Bus clock divider (SIM_CLKDIV1[OUTDIV4]).
+    outdiv4: u8,
+    /// FLL range (MCG_C4[DRST_DRS]) for FEI or FEE mode.
+    drst_drs: u8,
+    /// PLL settings for PEE mode, or `None` for FEI or FEE mode.
+    pll: Option<PllDividers>,
+}
+
+/// PLL and core clock divider settings for PEE mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PllDividers {
+    /// PLL reference divider (MCG_C5[PRDIV0]), divides by `prdiv0 + 1`.
+    prdiv0: u8,
+    /// PLL multiplier (MCG_C6[VDIV0]), multiplies by `vdiv0 + 24`.
+    vdiv0: u8,
+    /// Core clock divider (SIM_CLKDIV1[OUTDIV1]), divides by `outdiv1 + 1`.
+    outdiv1: u8,
+    /// Resulting PLL output (MCGPLLCLK) in Hz.
+    pll_clock: u32,
+    /// Resulting core clock in Hz.
+    core_clock: u32,
+}
+
+/// Searches the PLL and core clock divider settings which yield the highest core clock which is
+/// not higher than the requested clock.
+///
+/// The PLL reference clock has to be between 2 and 4 MHz and the PLL output has to be between 48
+/// and 100 MHz.
+fn get_pll_dividers(oscclk: Hertz, core_clock: Hertz) -> Option<PllDividers> {
+    let target = u32::min(core_clock.0, MAX_CORE_CLOCK);
+    let mut best: Option<PllDividers> = None;
+    for prdiv0 in 0..25u8 {
+        let reference = oscclk.0 / (prdiv0 as u32 + 1);
+        if !(2_000_000..=4_000_000).contains(&reference) {
+            continue;
+        }
+        for vdiv0 in 0..32u8 {
+            let pllclk = reference * (vdiv0 as u32 + 24);
+            if !(48_000_000..=100_000_000).contains(&pllclk) {
+                continue;
+            }
+            // Choose the smallest divider which does not exceed the requested frequency.
+            let outdiv1 = pllclk.div_ceil(u32::max(target, 1));
+            if outdiv1 > 16 {
+                continue;
+            }
+            let actual = pllclk / outdiv1;
+            if best.map(|b| actual > b.core_clock).unwrap_or(true) {
+                best = Some(PllDividers {
+                    prdiv0,
+                    vdiv0,
+                    outdiv1: (outdiv1 - 1) as u8,
+                    pll_clock: pllclk,
+                    core_clock: actual,
+                });
+            }
+        }
+    }
+    best
+}
+
+/// Checks the requested frequencies against the limits of the chip.
+fn check_clock_limits(core_clock: Hertz, bus_clock: Hertz) -> Result<(), ClockError> {
+    if core_clock.0 > MAX_CORE_CLOCK {
+        Err(ClockError::CoreClockTooHigh)
+    } else if bus_clock.0 > MAX_BUS_CLOCK {
+        Err(ClockError::BusClockTooHigh)
+    } else if bus_clock.0 > core_clock.0 {
+        Err(ClockError::BusClockAboveCoreClock)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the core clock divider (SIM_CLKDIV1[OUTDIV1]) and the FLL range (MCG_C4[DRST_DRS])
+/// for FEI mode.
+///
+/// We want to select a high FLL frequency if the user requested a high core frequency or if
+/// lower frequencies are not close to a multiple of it.
+fn get_fll_dividers(core_clock: Hertz) -> Result<(u8, u8), ClockError> {
+    Ok(match core_clock.0 {
+        0..5_800_000 => {
+            let outdiv1 = (640 * SLOW_IRC + core_clock.0 / 2) / u32::max(core_clock.0, 1);
+            if outdiv1 > 16 {
+                return Err(ClockError::CoreClockOutOfRange);
+            }
+            (outdiv1 as u8 - 1, 0)
+        }
+        5_800_000..6_400_000 => (6, 1),   // 5991862 Hz
+        6_400_000..6_700_000 => (9, 2),   // 6291456 Hz
+        6_700_000..7_500_000 => (2, 0),   // 6990506 Hz
+        7_500_000..8_000_000 => (7, 2),   // 7864320 Hz
+        8_000_000..8_700_000 => (4, 1),   // 8388608 Hz
+        8_700_000..9_800_000 => (6, 2),   // 8987794 Hz
+        9_800_000..11_600_000 => (1, 0),  // 10485760 Hz
+        11_600_000..13_300_000 => (4, 2), // 12582912 Hz
+        13_300_000..14_900_000 => (2, 1), // 13981013 Hz
+        14_900_000..18_400_000 => (3, 2), // 15728640 Hz
+        18_400_000..26_300_000 => (0, 0), // 20971520 Hz
+        26_300_000..36_800_000 => (1, 2), // 31457280 Hz
+        _ => (0, 1),                      // 41943040 Hz
+    })
+}
+
+/// Returns the core clock divider (SIM_CLKDIV1[OUTDIV1]) and the FLL range (MCG_C4[DRST_DRS])
+/// for FEE mode which yield the highest core clock which is not higher than the requested clock.
+fn get_fee_dividers(fll_reference: u32, core_clock: Hertz) -> Option<(u8, u8)> {
+    let target = u32::min(core_clock.0, MAX_CORE_CLOCK);
+    let mut best: Option<(u8, u8, u32)> = None;
+    for drst_drs in 0..4u8 {
+        let fllclk = fll_reference * [640, 1280, 1920, 2560][drst_drs as usize];
+        let outdiv1 = fllclk.div_ceil(u32::max(target, 1));
+        if outdiv1 > 16 {
+            continue;
+        }
+        let actual = fllclk / outdiv1;
+        if best.map(|b| actual > b.2).unwrap_or(true) {
+            best = Some(((outdiv1 - 1) as u8, drst_drs, actual));
+        }
+    }
+    best.map(|(outdiv1, drst_drs, _)| (outdiv1, drst_drs))
+}
+
+/// Returns the bus clock divider (SIM_CLKDIV1[OUTDIV4]) for the highest bus clock which does
+/// neither exceed the requested frequency nor the maximum bus clock.
+fn get_bus_divider(core_clock: u32, bus_clock: u32) -> Result<u8, ClockError> {
+    let target = u32::min(bus_clock, MAX_BUS_CLOCK);
+    let outdiv4 = core_clock.div_ceil(u32::max(target, 1));
+    if outdiv4 > 8 {
+        return Err(ClockError::BusClockOutOfRange);
+    }
+    Ok(outdiv4.max(1) as u8 - 1)
+}
+
+/// Returns the oscillator frequency range (MCG_C2[RANGE0]) for the external reference.
+fn get_oscillator_range(oscclk: Hertz) -> u8 {
+    match oscclk.0 {
+        0..1_000_000 => 0,
+        1_000_000..8_000_000 => 1,
+        _ => 2,
+    }
+}
+
+/// Returns the FLL external reference divider (MCG_C1[FRDIV]) which brings the FLL reference as
+/// close as possible to the required 31.25-39.0625 kHz range.
+fn get_fll_reference_divider(oscclk: Hertz, range: u8) -> u8 {
+    (0..8)
+        .position(|frdiv| oscclk.0 / get_fll_reference_divider_value(frdiv, range) <= 39_062)
+        .unwrap_or(7) as u8
+}
+
+/// Returns the factor by which the FLL external reference divider (MCG_C1[FRDIV]) divides.
+fn get_fll_reference_divider_value(frdiv: u8, range: u8) -> u32 {
+    match (frdiv, range) {
+        (_, 0) => 1 << frdiv,
+        (6, _) => 1280,
+        (7, _) => 1536,
+        _ => 32 << frdiv,
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -190,92 +1353,86 @@ enum ClockSource {
     External(Hertz, ExternalMode),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum ExternalMode {
     Crystal,
     Clock,
 }
 
-// TODO: This function should get the pins and a SIM reference as parameters.
-pub fn init() -> Clocks {
-    unsafe {
-        // Initialize the system clocks to a 48 Mhz core clock speed
-        // Mode progression:  FEI (reset) -> FBE -> PBE -> PEE
-        //
-        // Note:  Generated by Processor Expert, cleaned up by hand.
-        //        For detailed information on clock modes, see the
-        //        "KL25 Sub-Family Reference Manual" section 24.5.3
-
-        // Enable clock gate to Port A module to enable pin routing (PORTA=1)
-        (*SIM::ptr()).scgc5.modify(|_, w| w.porta().set_bit());
-
-        // Divide-by-2 for clock 1 and clock 4 (OUTDIV1=1, OUTDIV4=1)
-        (*SIM::ptr())
-            .clkdiv1
-            .modify(|_, w| w.outdiv1().bits(1).outdiv4().bits(1));
-
-        // System oscillator drives 32 kHz clock for various peripherals (OSC32KSEL=0)
-        (*SIM::ptr()).sopt1.modify(|_, w| w.osc32ksel().bits(0));
-
-        // Select PLL as a clock source for various peripherals (PLLFLLSEL=1)
-        // Clock source for TPM counter clock is MCGFLLCLK or MCGPLLCLK/2
-        (*SIM::ptr()).sopt2.modify(|_, w| w.pllfllsel().set_bit());
-        (*SIM::ptr()).sopt2.modify(|_, w| w.tpmsrc().bits(0x1));
-
-        /* PORTA_PCR18: ISF=0,MUX=0 */
-        /* PORTA_PCR19: ISF=0,MUX=0 */
-
-        (*PORTA::ptr()).pcr[18].modify(|_, w| w.isf().clear_bit().mux().bits(0x07));
-        (*PORTA::ptr()).pcr[19].modify(|_, w| w.isf().clear_bit().mux().bits(0x07));
-        /* Switch to FBE Mode */
-
-        /* OSC0_CR: ERCLKEN=0,??=0,EREFSTEN=0,??=0,SC2P=0,SC4P=0,SC8P=0,SC16P=0 */
-        (*OSC0::ptr()).cr.write(|w| w.bits(0));
-        /* MCG_C2: LOCRE0=0,??=0,RANGE0=2,HGO0=0,EREFS0=1,LP=0,IRCS=0 */
-        (*MCG::ptr())
-            .c2
-            .write(|w| w.bits(0).range0().bits(0x2).erefs0().set_bit());
-        /* MCG_C1: CLKS=2,FRDIV=3,IREFS=0,IRCLKEN=0,IREFSTEN=0 */
-        (*MCG::ptr())
-            .c1
-            .write(|w| w.bits(0).clks().bits(0x2).frdiv().bits(0x3));
-        /* MCG_C4: DMX32=0,DRST_DRS=0 */
-        (*MCG::ptr())
-            .c4
-            .modify(|_, w| w.dmx32().clear_bit().drst_drs().bits(0));
-        /* MCG_C5: ??=0,PLLCLKEN0=0,PLLSTEN0=0,PRDIV0=1 */
-        (*MCG::ptr()).c5.write(|w| w.bits(0).prdiv0().bits(0x1));
-        /* MCG_C6: LOLIE0=0,PLLS=0,CME0=0,VDIV0=0 */
-        (*MCG::ptr()).c6.write(|w| w.bits(0));
+#[derive(Clone, Copy)]
+enum InternalReference {
+    Slow,
+    /// Fast IRC with the divider MCG_SC[FCRDIV].
+    Fast(u8),
+}
 
-        // Check that the source of the FLL reference clock is
-        // the external reference clock.
-        while (*MCG::ptr()).s.read().irefst().bit_is_set() {}
+#[derive(Clone, Copy)]
+enum Erclk32kSource {
+    Oscillator,
+    RtcClkin(Hertz),
+    Lpo,
+}
 
-        while (*MCG::ptr()).s.read().clkst().bits() != 0x2 {
-            // Wait until external reference
-        }
+/// Clock which can be routed to the CLKOUT pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockOutputSource {
+    /// Bus clock.
+    BusClock,
+    /// Low power oscillator (1 kHz).
+    Lpo,
+    /// MCG internal reference clock.
+    Mcgirclk,
+    /// System oscillator output.
+    Oscerclk,
+}
 
-        // Switch to PBE mode
-        //   Select PLL as MCG source (PLLS=1)
-        (*MCG::ptr()).c6.write(|w| w.bits(0).plls().set_bit());
-        while (*MCG::ptr()).s.read().lock0().bit_is_clear() {
-            // Wait until PLL locked
-        }
+/// Clock output on the CLKOUT pin (PTC3) for debugging the clock tree.
+pub struct ClockOutput {
+    pin: gpioc::PC3<gpio::Alternate5>,
+    frequency: Hertz,
+}
 
-        // Switch to PEE mode
-        //    Select PLL output (CLKS=0)
-        //    FLL external reference divider (FRDIV=3)
-        //    External reference clock for FLL (IREFS=0)
-        (*MCG::ptr()).c1.write(|w| w.bits(0).frdiv().bits(0x3));
-        while (*MCG::ptr()).s.read().clkst().bits() != 0x3 {
-            // Wait until PLL output
+impl ClockOutput {
+    /// Routes the selected clock to the CLKOUT pin.
+    ///
+    /// An error is returned if the selected clock is not enabled.
+    pub fn new(
+        pin: gpioc::PC3<gpio::Alternate5>,
+        source: ClockOutputSource,
+        clocks: Clocks,
+        sim: &mut SIM,
+    ) -> Result<Self, ClockError> {
+        let frequency = match source {
+            ClockOutputSource::BusClock => Some(clocks.busclk()),
+            ClockOutputSource::Lpo => Some(clocks.lpo()),
+            ClockOutputSource::Mcgirclk => clocks.mcgirclk(),
+            ClockOutputSource::Oscerclk => clocks.oscerclk(),
         }
+        .ok_or(ClockError::ClockDisabled)?;
+
+        sim.sopt2.modify(|_, w| {
+            let w = w.clkoutsel();
+            match source {
+                ClockOutputSource::BusClock => w._010(),
+                ClockOutputSource::Lpo => w._011(),
+                ClockOutputSource::Mcgirclk => w._100(),
+                ClockOutputSource::Oscerclk => w._110(),
+            }
+        });
+
+        Ok(ClockOutput { pin, frequency })
     }
 
-    Clocks {
-        coreclk: 48000000_u32.hz(),
-        busclk: 18000000_u32.hz(), // TODO: Typo?
+    /// Returns the frequency of the clock on the CLKOUT pin.
+    pub fn frequency(&self) -> Hertz {
+        self.frequency
+    }
+
+    /// Disables the clock output and releases the pin.
+    pub fn free(self, sim: &mut SIM) -> gpioc::PC3<gpio::Alternate5> {
+        // CLKOUTSEL is reset to 0, which does not select any clock.
+        sim.sopt2.modify(|_, w| unsafe { w.clkoutsel().bits(0) });
+        self.pin
     }
 }
 
@@ -284,6 +1441,12 @@ pub struct Clocks {
     // TODO: Rename core/bus.
     coreclk: Hertz,
     busclk: Hertz,
+    mcgirclk: Option<Hertz>,
+    oscerclk: Option<Hertz>,
+    erclk32k: Option<Hertz>,
+    mcgfllclk: Option<Hertz>,
+    mcgpllclk_div2: Option<Hertz>,
+    pllfllsel: bool,
 }
 
 impl Clocks {
@@ -294,4 +1457,439 @@ impl Clocks {
     pub fn busclk(&self) -> Hertz {
         self.busclk
     }
+
+    /// Returns the frequency of the MCG internal reference clock, if enabled.
+    pub fn mcgirclk(&self) -> Option<Hertz> {
+        self.mcgirclk
+    }
+
+    /// Returns the frequency of the system oscillator output, if enabled.
+    pub fn oscerclk(&self) -> Option<Hertz> {
+        self.oscerclk
+    }
+
+    /// Returns the frequency of the 32 kHz clock for the RTC and LPTMR, if available.
+    pub fn erclk32k(&self) -> Option<Hertz> {
+        self.erclk32k
+    }
+
+    /// Returns the frequency of the FLL output, if the FLL is enabled.
+    pub fn mcgfllclk(&self) -> Option<Hertz> {
+        self.mcgfllclk
+    }
+
+    /// Returns half the frequency of the PLL output, if the PLL is enabled.
+    pub fn mcgpllclk_div2(&self) -> Option<Hertz> {
+        self.mcgpllclk_div2
+    }
+
+    /// Returns the frequency of MCGFLLCLK or MCGPLLCLK/2, whichever is selected as the clock
+    /// source for the TPM and UART0 modules.
+    pub fn pllfllclk(&self) -> Option<Hertz> {
+        if self.pllfllsel {
+            self.mcgpllclk_div2
+        } else {
+            self.mcgfllclk
+        }
+    }
+
+    /// Returns the frequency of the low power oscillator (1 kHz), which is always available.
+    pub fn lpo(&self) -> Hertz {
+        LPO.hz()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pll_dividers() {
+        // FRDM-KL25Z: 8 MHz crystal, 48 MHz core clock.
+        assert_eq!(
+            get_pll_dividers(8_000_000.hz(), 48_000_000.hz()),
+            Some(PllDividers {
+                prdiv0: 1,
+                vdiv0: 0,
+                outdiv1: 1,
+                pll_clock: 96_000_000,
+                core_clock: 48_000_000,
+            })
+        );
+        // Requests above the maximum are clamped.
+        assert_eq!(
+            get_pll_dividers(8_000_000.hz(), 100_000_000.hz()).map(|p| p.core_clock),
+            Some(48_000_000)
+        );
+        assert_eq!(
+            get_pll_dividers(12_000_000.hz(), 41_000_000.hz()).map(|p| p.core_clock),
+            Some(41_000_000)
+        );
+        // Frequencies which cannot be reached exactly are rounded down.
+        assert_eq!(
+            get_pll_dividers(8_000_000.hz(), 47_500_000.hz()).map(|p| p.core_clock),
+            Some(47_000_000)
+        );
+        // The PLL output cannot be divided further than by 16.
+        assert_eq!(get_pll_dividers(8_000_000.hz(), 2_000_000.hz()), None);
+        // 32 kHz crystals cannot be used as a PLL reference.
+        assert_eq!(get_pll_dividers(32_768.hz(), 48_000_000.hz()), None);
+        // A zero core clock is rejected instead of dividing by zero.
+        assert_eq!(get_pll_dividers(8_000_000.hz(), 0.hz()), None);
+        let config = ClockConfiguration {
+            clock_source: ClockSource::External(8_000_000.hz(), ExternalMode::Crystal),
+            ..ClockConfiguration::new()
+        }
+        .core_clock(0.hz());
+        assert_eq!(config.check(), Err(ClockError::CoreClockOutOfRange));
+    }
+
+    #[test]
+    fn test_mode_transitions() {
+        use McgMode::*;
+        assert!(is_legal_transition(FEI, FBE));
+        assert!(is_legal_transition(PBE, BLPE));
+        assert!(!is_legal_transition(PEE, FBE));
+        assert!(!is_legal_transition(BLPI, BLPE));
+        assert!(!is_legal_transition(FEI, PEE));
+        // The graph is undirected.
+        for &from in &[FEI, FEE, FBI, FBE, PBE, PEE, BLPI, BLPE] {
+            for &to in from.neighbours() {
+                assert!(is_legal_transition(to, from));
+            }
+        }
+        // Shortest paths.
+        assert_eq!(next_mode(FEI, PEE), FBE);
+        assert_eq!(next_mode(FBE, PEE), PBE);
+        assert_eq!(next_mode(PEE, BLPI), PBE);
+        assert_eq!(next_mode(PBE, BLPI), FBE);
+        assert_eq!(next_mode(FBE, BLPI), FBI);
+        assert_eq!(next_mode(BLPE, PEE), PBE);
+        assert_eq!(next_mode(BLPI, FEE), FBI);
+        assert_eq!(next_mode(FBI, FEE), FEE);
+    }
+
+    /// MCG settings for an 8 MHz crystal.
+    fn crystal_settings() -> McgSettings {
+        McgSettings {
+            oscclk: Some((8_000_000, ExternalMode::Crystal)),
+            frdiv: 3,
+            drst_drs: 0,
+            slow_irc: SLOW_IRC,
+            fast_irc: FAST_IRC,
+            irc: InternalReference::Fast(0),
+            pll: get_pll_dividers(8_000_000.hz(), 48_000_000.hz()),
+        }
+    }
+
+    #[test]
+    fn test_free_round_trip() {
+        use McgMode::*;
+        // Mcg::free() can return to FEI mode from every mode.
+        let crystal = crystal_settings();
+        for &from in &[FEI, FEE, FBI, FBE, PBE, PEE, BLPI, BLPE] {
+            let mut mode = from;
+            while mode != FEI {
+                let next = next_mode(mode, FEI);
+                assert!(is_legal_transition(mode, next));
+                assert_eq!(crystal.check(next), Ok(()));
+                mode = next;
+            }
+        }
+        // The reset dividers are restored, also with a trimmed slow IRC.
+        assert_eq!(get_reset_dividers(SLOW_IRC), (RESET_OUTDIV1, RESET_OUTDIV4));
+        assert_eq!(get_reset_dividers(39_062), (RESET_OUTDIV1, RESET_OUTDIV4));
+        assert_eq!(get_reset_dividers(31_250), (RESET_OUTDIV1, RESET_OUTDIV4));
+        // ... and result in the reset clock frequencies.
+        let (outdiv1, outdiv4) = get_reset_dividers(SLOW_IRC);
+        let coreclk = crystal.mcgoutclk(FEI).unwrap() / (outdiv1 as u32 + 1);
+        assert_eq!(coreclk, 20_971_520);
+        assert_eq!(coreclk / (outdiv4 as u32 + 1), 10_485_760);
+    }
+
+    #[test]
+    fn test_mode_frequencies() {
+        let internal = McgSettings {
+            oscclk: None,
+            frdiv: 0,
+            drst_drs: 0,
+            slow_irc: SLOW_IRC,
+            fast_irc: FAST_IRC,
+            irc: InternalReference::Slow,
+            pll: None,
+        };
+        assert_eq!(internal.mcgoutclk(McgMode::FEI), Ok(20_971_520));
+        assert_eq!(internal.mcgoutclk(McgMode::BLPI), Ok(32_768));
+        assert_eq!(
+            internal.mcgoutclk(McgMode::FBE),
+            Err(ClockError::NoExternalReference)
+        );
+
+        let crystal = crystal_settings();
+        assert_eq!(crystal.mcgoutclk(McgMode::PEE), Ok(96_000_000));
+        assert_eq!(crystal.mcgoutclk(McgMode::BLPE), Ok(8_000_000));
+        assert_eq!(crystal.mcgoutclk(McgMode::FBI), Ok(4_000_000));
+        assert_eq!(crystal.mcgoutclk(McgMode::FEE), Ok(20_000_000));
+        assert_eq!(crystal.fll_clock(McgMode::FBE), Some(20_000_000));
+        assert_eq!(crystal.fll_clock(McgMode::PBE), None);
+        assert_eq!(crystal.pll_clock(McgMode::PBE), Some(96_000_000));
+
+        let slow_clock = McgSettings {
+            oscclk: Some((32_768, ExternalMode::Clock)),
+            frdiv: 0,
+            drst_drs: 0,
+            slow_irc: SLOW_IRC,
+            fast_irc: FAST_IRC,
+            irc: InternalReference::Slow,
+            pll: None,
+        };
+        assert_eq!(slow_clock.mcgoutclk(McgMode::FEE), Ok(20_971_520));
+        assert_eq!(slow_clock.mcgoutclk(McgMode::PBE), Err(ClockError::NoPll));
+        assert_eq!(slow_clock.check_path(McgMode::FEI, McgMode::FEE), Ok(()));
+        assert_eq!(
+            slow_clock.check_path(McgMode::FEI, McgMode::PEE),
+            Err(ClockError::NoPll)
+        );
+
+        // Trimmed internal reference clocks.
+        let trimmed = McgSettings {
+            oscclk: None,
+            frdiv: 0,
+            drst_drs: 0,
+            slow_irc: 32_000,
+            fast_irc: 4_200_000,
+            irc: InternalReference::Fast(1),
+            pll: None,
+        };
+        assert_eq!(trimmed.mcgoutclk(McgMode::FEI), Ok(20_480_000));
+        assert_eq!(trimmed.mcgoutclk(McgMode::BLPI), Ok(2_100_000));
+    }
+
+    #[test]
+    fn test_auto_trim_compare_value() {
+        assert_eq!(
+            get_auto_trim_compare_value(12_000_000, Irc::Slow, 32_768),
+            Ok(7690)
+        );
+        assert_eq!(
+            get_auto_trim_compare_value(12_000_000, Irc::Fast, 4_000_000),
+            Ok(8064)
+        );
+        assert_eq!(
+            get_auto_trim_compare_value(24_000_000, Irc::Slow, 32_768),
+            Err(ClockError::TrimReferenceOutOfRange)
+        );
+        assert_eq!(
+            get_auto_trim_compare_value(12_000_000, Irc::Slow, 40_000),
+            Err(ClockError::TrimOutOfRange)
+        );
+        assert_eq!(
+            get_auto_trim_compare_value(16_000_000, Irc::Fast, 6_000_000),
+            Err(ClockError::TrimOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_output_dividers() {
+        // Switching from FEI to PEE requires larger dividers.
+        assert_eq!(get_output_dividers(96_000_000, 0, 1), (1, 1));
+        // Switching from PEE to BLPI keeps the dividers.
+        assert_eq!(get_output_dividers(4_000_000, 1, 1), (1, 1));
+        assert_eq!(get_output_dividers(20_971_520, 0, 0), (0, 0));
+        assert_eq!(get_output_dividers(41_943_040, 0, 0), (0, 1));
+    }
+
+    #[test]
+    fn test_external_startup_dividers() {
+        // The dividers for PEE mode are set before leaving FEI mode, so they have to be valid
+        // for FEI, FBE and PBE mode as well.
+        let config = ClockConfiguration {
+            clock_source: ClockSource::External(8_000_000.hz(), ExternalMode::Crystal),
+            ..ClockConfiguration::new()
+        }
+        .core_clock(48.mhz());
+        let dividers = config.dividers().unwrap();
+        let (outdiv1, outdiv4) = (dividers.outdiv1, dividers.outdiv4);
+        assert_eq!((outdiv1, outdiv4), (1, 1));
+        for &mcgoutclk in [20_971_520, 8_000_000, 96_000_000].iter() {
+            assert_eq!(
+                get_output_dividers(mcgoutclk, outdiv1, outdiv4),
+                (outdiv1, outdiv4)
+            );
+        }
+    }
+
+    #[test]
+    fn test_32khz_crystal() {
+        // A 32.768 kHz crystal cannot be used for the PLL, so it drives the FLL in FEE mode.
+        let config = ClockConfiguration {
+            clock_source: ClockSource::External(32_768.hz(), ExternalMode::Crystal),
+            ..ClockConfiguration::new()
+        }
+        .core_clock(48.mhz())
+        .erclk32k_from_oscillator();
+        let dividers = config.dividers().unwrap();
+        assert_eq!(dividers.pll, None);
+        assert_eq!(
+            (dividers.outdiv1, dividers.outdiv4, dividers.drst_drs),
+            (0, 1, 1)
+        );
+        assert_eq!(config.erclk32k_frequency().map(|freq| freq.0), Some(32_768));
+
+        // OSC32KCLK is not available with a high frequency crystal.
+        let config = ClockConfiguration {
+            clock_source: ClockSource::External(8_000_000.hz(), ExternalMode::Crystal),
+            ..ClockConfiguration::new()
+        }
+        .erclk32k_from_oscillator();
+        assert_eq!(config.erclk32k_frequency().map(|freq| freq.0), None);
+
+        // References which can be used neither for the PLL nor for the FLL are rejected.
+        let config = ClockConfiguration {
+            clock_source: ClockSource::External(1_500_000.hz(), ExternalMode::Clock),
+            ..ClockConfiguration::new()
+        };
+        assert_eq!(config.check(), Err(ClockError::ReferenceOutOfRange));
+    }
+
+    #[test]
+    fn test_fee_dividers() {
+        assert_eq!(get_fee_dividers(32_768, 48.mhz().into()), Some((0, 1)));
+        assert_eq!(get_fee_dividers(32_768, 24.mhz().into()), Some((0, 0)));
+        assert_eq!(get_fee_dividers(32_768, 30.mhz().into()), Some((2, 3)));
+        assert_eq!(get_fee_dividers(32_768, 1.mhz().into()), None);
+    }
+
+    #[test]
+    fn test_bus_divider() {
+        assert_eq!(get_bus_divider(48_000_000, 24_000_000), Ok(1));
+        assert_eq!(get_bus_divider(48_000_000, 48_000_000), Ok(1));
+        assert_eq!(get_bus_divider(48_000_000, 20_000_000), Ok(2));
+        assert_eq!(get_bus_divider(20_971_520, 24_000_000), Ok(0));
+        assert_eq!(get_bus_divider(48_000_000, 6_000_000), Ok(7));
+        assert_eq!(
+            get_bus_divider(48_000_000, 1_000_000),
+            Err(ClockError::BusClockOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_clock_limits() {
+        assert_eq!(check_clock_limits(48.mhz().into(), 24.mhz().into()), Ok(()));
+        assert_eq!(
+            check_clock_limits(50.mhz().into(), 24.mhz().into()),
+            Err(ClockError::CoreClockTooHigh)
+        );
+        assert_eq!(
+            check_clock_limits(48.mhz().into(), 48.mhz().into()),
+            Err(ClockError::BusClockTooHigh)
+        );
+        assert_eq!(
+            check_clock_limits(8.mhz().into(), 12.mhz().into()),
+            Err(ClockError::BusClockAboveCoreClock)
+        );
+    }
+
+    #[test]
+    fn test_default_bus_clock() {
+        // The bus clock follows a low core clock if it is not set.
+        let config = ClockConfiguration::new().core_clock(8.mhz());
+        assert_eq!(config.check(), Ok(()));
+        assert_eq!(
+            config.dividers().map(|d| (d.outdiv1, d.outdiv4)),
+            Ok((4, 0))
+        );
+        let config = ClockConfiguration::new().use_irc().core_clock(2.mhz());
+        assert_eq!(config.dividers().map(|d| d.outdiv4), Ok(0));
+        // It is limited to the maximum bus clock otherwise.
+        let config = ClockConfiguration::new().core_clock(48.mhz());
+        assert_eq!(
+            config.dividers().map(|d| (d.outdiv1, d.outdiv4)),
+            Ok((0, 1))
+        );
+        assert_eq!(
+            ClockConfiguration::new()
+                .core_clock(8.mhz())
+                .bus_clock(12.mhz())
+                .check(),
+            Err(ClockError::BusClockAboveCoreClock)
+        );
+    }
+
+    #[test]
+    fn test_trimmed_irc_limits() {
+        let trim = IrcTrim {
+            sctrim: 0x80,
+            scftrim: false,
+            fctrim: 8,
+        };
+        // A fast slow IRC would result in a 49.92 MHz core clock, so the core clock is halved.
+        let config = ClockConfiguration::new()
+            .trim_irc(trim, 39_000.hz(), 4.mhz())
+            .core_clock(48.mhz());
+        assert_eq!(config.check(), Ok(()));
+        assert_eq!(
+            config
+                .dividers()
+                .map(|d| (d.outdiv1, d.drst_drs, d.outdiv4)),
+            Ok((1, 1, 1))
+        );
+        // The nominal IRC does not need a larger divider.
+        let config = ClockConfiguration::new()
+            .trim_irc(trim, 32_768.hz(), 4.mhz())
+            .core_clock(48.mhz());
+        assert_eq!(config.dividers().map(|d| d.outdiv1), Ok(0));
+    }
+
+    #[test]
+    fn test_fll_dividers() {
+        let fll_clock = |core_clock: u32| {
+            get_fll_dividers(core_clock.hz()).map(|(outdiv1, drst_drs)| {
+                [640, 1280, 1920, 2560][drst_drs as usize] * SLOW_IRC / (outdiv1 as u32 + 1)
+            })
+        };
+        // Every entry of the table, checked at both ends of its range.
+        let table = [
+            (5_800_000, 6_400_000, 5_991_862),
+            (6_400_000, 6_700_000, 6_291_456),
+            (6_700_000, 7_500_000, 6_990_506),
+            (7_500_000, 8_000_000, 7_864_320),
+            (8_000_000, 8_700_000, 8_388_608),
+            (8_700_000, 9_800_000, 8_987_794),
+            (9_800_000, 11_600_000, 10_485_760),
+            (11_600_000, 13_300_000, 12_582_912),
+            (13_300_000, 14_900_000, 13_981_013),
+            (14_900_000, 18_400_000, 15_728_640),
+            (18_400_000, 26_300_000, 20_971_520),
+            (26_300_000, 36_800_000, 31_457_280),
+            (36_800_000, 48_000_001, 41_943_040),
+        ];
+        for &(start, end, expected) in table.iter() {
+            assert_eq!(fll_clock(start), Ok(expected));
+            assert_eq!(fll_clock(end - 1), Ok(expected));
+        }
+        // Low frequencies are generated by dividing the lowest FLL frequency.
+        assert_eq!(get_fll_dividers(5_000_000.hz()), Ok((3, 0)));
+        assert_eq!(get_fll_dividers(2_000_000.hz()), Ok((9, 0)));
+        assert_eq!(fll_clock(1_310_720), Ok(1_310_720));
+        assert_eq!(
+            get_fll_dividers(1_000_000.hz()),
+            Err(ClockError::CoreClockOutOfRange)
+        );
+        assert_eq!(
+            get_fll_dividers(0.hz()),
+            Err(ClockError::CoreClockOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_fll_reference_divider() {
+        assert_eq!(get_oscillator_range(32_768.hz()), 0);
+        assert_eq!(get_oscillator_range(4_000_000.hz()), 1);
+        assert_eq!(get_oscillator_range(8_000_000.hz()), 2);
+        assert_eq!(get_fll_reference_divider(32_768.hz(), 0), 0);
+        assert_eq!(get_fll_reference_divider(4_000_000.hz(), 1), 2);
+        assert_eq!(get_fll_reference_divider(8_000_000.hz(), 2), 3);
+        assert_eq!(get_fll_reference_divider(32_000_000.hz(), 2), 5);
+    }
 }
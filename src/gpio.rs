@@ -31,7 +31,10 @@
 
 use core::marker::PhantomData;
 
-use mkl25z4::SIM;
+use crate::hal::digital::v2::{
+    toggleable, InputPin, OutputPin, PinState, StatefulOutputPin, ToggleableOutputPin,
+};
+use mkl25z4::{GPIOA, GPIOB, GPIOC, GPIOD, GPIOE, PORTA, PORTB, PORTC, PORTD, PORTE, SIM};
 
 /// Extension trait to split a GPIO peripheral in independent pins and registers
 pub trait GpioExt {
@@ -64,6 +67,45 @@ pub struct PushPull;
 /// Open drain output (type state)
 pub struct OpenDrain;
 
+/// Pin which can be switched between input and output at runtime (type state)
+pub struct Dynamic;
+
+/// Pull resistor configuration of a dynamic pin in input mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    /// No pull resistor
+    None,
+    /// Pull-up resistor
+    Up,
+    /// Pull-down resistor
+    Down,
+}
+
+/// Output drive strength of a pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DriveStrength {
+    /// Normal drive strength
+    Normal,
+    /// High drive strength
+    High,
+}
+
+/// Output slew rate of a pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlewRate {
+    /// Fast slew rate
+    Fast,
+    /// Slow slew rate
+    Slow,
+}
+
+/// Error returned when a dynamic pin is used in the wrong direction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinModeError {
+    /// The pin is configured as output but was read as input, or vice versa
+    IncorrectMode,
+}
+
 pub trait Alternate {
     const MUX: u8;
 }
@@ -104,28 +146,613 @@ impl Alternate for Alternate7 {
     const MUX: u8 = 7;
 }
 
+/// Pin accessed through the single-cycle IOPORT interface (FGPIO)
+///
+/// The IOPORT is an alias of the GPIO registers on the core's local bus, so the pin state can be
+/// read and written within a single cycle instead of going through the peripheral bridge. Only
+/// the core can access the IOPORT, DMA transfers still have to use the GPIO registers.
+pub struct Fast<PIN> {
+    pin: PIN,
+}
+
+impl<PIN> Fast<PIN> {
+    /// Returns the pin, routing accesses through the peripheral bridge again
+    pub fn into_inner(self) -> PIN {
+        self.pin
+    }
+}
+
+/// Interrupt condition of a pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// Interrupt on rising edge
+    Rising,
+    /// Interrupt on falling edge
+    Falling,
+    /// Interrupt on either edge
+    RisingFalling,
+    /// Interrupt while the pin is low
+    LogicZero,
+    /// Interrupt while the pin is high
+    LogicOne,
+}
+
+impl Edge {
+    fn irqc(self) -> u8 {
+        match self {
+            Edge::Rising => 0b1001,
+            Edge::Falling => 0b1010,
+            Edge::RisingFalling => 0b1011,
+            Edge::LogicZero => 0b1000,
+            Edge::LogicOne => 0b1100,
+        }
+    }
+}
+
+/// External interrupt configuration of an input pin
+///
+/// Only the pins of ports A and D can generate interrupts. All pins of a port share a single
+/// interrupt vector, so the handler has to check which pin caused the interrupt.
+pub trait ExtiPin {
+    /// Enables the interrupt of the pin for the specified condition
+    fn enable_interrupt(&mut self, edge: Edge);
+
+    /// Disables the interrupt of the pin
+    fn disable_interrupt(&mut self);
+
+    /// Returns whether the interrupt status flag of the pin is set
+    fn check_interrupt(&self) -> bool;
+
+    /// Clears the interrupt status flag of the pin
+    fn clear_interrupt_pending_bit(&mut self);
+}
+
+/// Edge of a pin which triggers a DMA request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DmaTrigger {
+    /// DMA request on rising edge
+    Rising,
+    /// DMA request on falling edge
+    Falling,
+    /// DMA request on either edge
+    RisingFalling,
+}
+
+impl DmaTrigger {
+    fn irqc(self) -> u8 {
+        match self {
+            DmaTrigger::Rising => 0b0001,
+            DmaTrigger::Falling => 0b0010,
+            DmaTrigger::RisingFalling => 0b0011,
+        }
+    }
+}
+
+/// DMAMUX request source of a port
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DmaSource {
+    source: u8,
+}
+
+impl DmaSource {
+    /// Returns the source number to be written to the DMAMUX channel configuration register
+    pub fn source(self) -> u8 {
+        self.source
+    }
+}
+
+/// DMA request configuration of an input pin
+///
+/// Only the pins of ports A and D can generate DMA requests. The DMA request and the interrupt
+/// of a pin are configured by the same PCR field, so enabling one disables the other. All pins
+/// of a port share a single DMA request source.
+pub trait DmaPin {
+    /// Enables the DMA request of the pin and returns the DMAMUX source of the port
+    fn enable_dma_request(&mut self, trigger: DmaTrigger) -> DmaSource;
+
+    /// Disables the DMA request of the pin
+    fn disable_dma_request(&mut self);
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    /// GPIO registers of a port used by `PortGroup`
+    pub trait Port {
+        fn set(mask: u32);
+        fn clear(mask: u32);
+        fn input() -> u32;
+    }
+}
+
+/// Typed GPIO pin that can be part of a `PortGroup`
+pub trait GroupPin: sealed::Sealed {
+    /// GPIO peripheral of the port of the pin
+    type Port: sealed::Port;
+    /// Bit of the pin in the GPIO registers
+    const MASK: u32;
+}
+
+/// Typed push pull output pin that can be part of a `PortGroup`
+pub trait GroupOutputPin: GroupPin {}
+
+/// Tuple of pins of a single port that can be used as a `PortGroup`
+pub trait GroupPins: sealed::Sealed {
+    /// GPIO peripheral of the port of the pins
+    type Port: sealed::Port;
+    /// Bits of all pins in the GPIO registers
+    const MASK: u32;
+
+    /// Maps bit `k` of `value` to the port bit of the `k`-th pin
+    fn spread(value: u32) -> u32;
+
+    /// Maps the port bit of the `k`-th pin to bit `k` of the result
+    fn gather(bits: u32) -> u32;
+}
+
+/// Tuple of push pull output pins of a single port
+pub trait GroupOutputPins: GroupPins {}
+
+macro_rules! group_pins {
+    ($P0:ident: $k0:tt $(, $P:ident: $k:tt)*) => {
+        impl<$P0: GroupPin $(, $P: GroupPin<Port = $P0::Port>)*> sealed::Sealed for ($P0, $($P,)*) {}
+
+        impl<$P0: GroupPin $(, $P: GroupPin<Port = $P0::Port>)*> GroupPins for ($P0, $($P,)*) {
+            type Port = $P0::Port;
+            const MASK: u32 = $P0::MASK $(| $P::MASK)*;
+
+            fn spread(value: u32) -> u32 {
+                let mut bits = 0;
+                if value & (1 << $k0) != 0 {
+                    bits |= $P0::MASK;
+                }
+                $(
+                    if value & (1 << $k) != 0 {
+                        bits |= $P::MASK;
+                    }
+                )*
+                bits
+            }
+
+            fn gather(bits: u32) -> u32 {
+                let mut value = 0;
+                if bits & $P0::MASK != 0 {
+                    value |= 1 << $k0;
+                }
+                $(
+                    if bits & $P::MASK != 0 {
+                        value |= 1 << $k;
+                    }
+                )*
+                value
+            }
+        }
+
+        impl<$P0: GroupOutputPin $(, $P: GroupOutputPin<Port = $P0::Port>)*> GroupOutputPins
+            for ($P0, $($P,)*)
+        {
+        }
+    };
+}
+
+group_pins!(P0: 0);
+group_pins!(P0: 0, P1: 1);
+group_pins!(P0: 0, P1: 1, P2: 2);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7, P8: 8);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7, P8: 8, P9: 9);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7, P8: 8, P9: 9, P10: 10);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7, P8: 8, P9: 9, P10: 10, P11: 11);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7, P8: 8, P9: 9, P10: 10, P11: 11, P12: 12);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7, P8: 8, P9: 9, P10: 10, P11: 11, P12: 12, P13: 13);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7, P8: 8, P9: 9, P10: 10, P11: 11, P12: 12, P13: 13, P14: 14);
+group_pins!(P0: 0, P1: 1, P2: 2, P3: 3, P4: 4, P5: 5, P6: 6, P7: 7, P8: 8, P9: 9, P10: 10, P11: 11, P12: 12, P13: 13, P14: 14, P15: 15);
+
+/// Group of pins of a single port which are accessed at once
+///
+/// The group is created from a tuple of typed pins, e.g. `(pd0, pd1, ..., pd7)`, and bit `k` of
+/// the values written and read corresponds to the `k`-th pin of the tuple. Only pins of the same
+/// port can be combined, and as every pin is moved into the group, no pin can be used twice.
+pub struct PortGroup<PINS> {
+    pins: PINS,
+}
+
+impl<PINS: GroupPins> PortGroup<PINS> {
+    /// Combines the pins into a group
+    pub fn new(pins: PINS) -> Self {
+        PortGroup { pins }
+    }
+
+    /// Reads the state of all pins with a single PDIR access
+    pub fn read(&self) -> u32 {
+        PINS::gather(<PINS::Port as sealed::Port>::input())
+    }
+
+    /// Releases the pins
+    pub fn free(self) -> PINS {
+        self.pins
+    }
+}
+
+impl<PINS: GroupOutputPins> PortGroup<PINS> {
+    /// Writes the outputs of all pins
+    ///
+    /// The pins which are set are written with PSOR first, followed by the pins which are
+    /// cleared with PCOR.
+    pub fn write(&mut self, value: u32) {
+        let set = PINS::spread(value);
+        <PINS::Port as sealed::Port>::set(set);
+        <PINS::Port as sealed::Port>::clear(PINS::MASK & !set);
+    }
+}
+
+/// Fully erased pin
+///
+/// Pins of different ports can be downgraded to this type to store them in a single array.
+pub struct Pin<MODE> {
+    port: u8,
+    i: u8,
+    _mode: PhantomData<MODE>,
+}
+
+impl<MODE> Pin<MODE> {
+    fn gpio(&self) -> &'static mkl25z4::gpioa::RegisterBlock {
+        // NOTE(unsafe) the register blocks of all ports have the same layout, and the pin only
+        // accesses its own bit
+        unsafe {
+            match self.port {
+                0 => &*GPIOA::ptr(),
+                1 => &*(GPIOB::ptr() as *const mkl25z4::gpioa::RegisterBlock),
+                2 => &*(GPIOC::ptr() as *const mkl25z4::gpioa::RegisterBlock),
+                3 => &*(GPIOD::ptr() as *const mkl25z4::gpioa::RegisterBlock),
+                _ => &*(GPIOE::ptr() as *const mkl25z4::gpioa::RegisterBlock),
+            }
+        }
+    }
+
+    fn pcr(&self) -> &'static mkl25z4::porta::PCR {
+        // NOTE(unsafe) the register blocks of all ports have the same layout, and the pin only
+        // accesses its own PCR
+        let port = unsafe {
+            match self.port {
+                0 => &*PORTA::ptr(),
+                1 => &*(PORTB::ptr() as *const mkl25z4::porta::RegisterBlock),
+                2 => &*(PORTC::ptr() as *const mkl25z4::porta::RegisterBlock),
+                3 => &*(PORTD::ptr() as *const mkl25z4::porta::RegisterBlock),
+                _ => &*(PORTE::ptr() as *const mkl25z4::porta::RegisterBlock),
+            }
+        };
+        &port.pcr[self.i as usize]
+    }
+}
+
+impl Pin<Dynamic> {
+    /// Configures the pin as input with the specified pull resistor
+    pub fn make_input(&mut self, pull: Pull) {
+        cortex_m::interrupt::free(|_| unsafe {
+            // Configure GPIO as input.
+            self.gpio()
+                .pddr
+                .modify(|r, w| w.pdd().bits(r.pdd().bits() & !(1 << self.i)));
+        });
+        self.pcr().modify(|_, w| {
+            let w = w.isf().clear_bit().irqc()._0000().mux()._001();
+            match pull {
+                Pull::None => w.pe().clear_bit(),
+                Pull::Up => w.pe().set_bit().ps().set_bit(),
+                Pull::Down => w.pe().set_bit().ps().clear_bit(),
+            }
+        });
+    }
+
+    /// Configures the pin as push pull output with the specified initial state
+    pub fn make_output(&mut self, initial_state: PinState) {
+        // NOTE(unsafe) atomic write to a stateless register
+        match initial_state {
+            PinState::High => unsafe { self.gpio().psor.write(|w| w.bits(1 << self.i)) },
+            PinState::Low => unsafe { self.gpio().pcor.write(|w| w.bits(1 << self.i)) },
+        }
+        self.pcr().modify(|_, w| {
+            w.isf()
+                .clear_bit()
+                .irqc()
+                ._0000()
+                .mux()
+                ._001()
+                .pe()
+                .clear_bit()
+        });
+        cortex_m::interrupt::free(|_| unsafe {
+            // Configure GPIO as output.
+            self.gpio()
+                .pddr
+                .modify(|r, w| w.pdd().bits(r.pdd().bits() | (1 << self.i)));
+        });
+    }
+
+    /// Returns whether the pin is currently configured as output
+    pub fn is_output(&self) -> bool {
+        self.gpio().pddr.read().bits() & (1 << self.i) != 0
+    }
+}
+
+impl OutputPin for Pin<Dynamic> {
+    type Error = PinModeError;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        if !self.is_output() {
+            return Err(PinModeError::IncorrectMode);
+        }
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe { self.gpio().psor.write(|w| w.bits(1 << self.i)) }
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        if !self.is_output() {
+            return Err(PinModeError::IncorrectMode);
+        }
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe { self.gpio().pcor.write(|w| w.bits(1 << self.i)) }
+        Ok(())
+    }
+}
+
+impl InputPin for Pin<Dynamic> {
+    type Error = PinModeError;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.is_low().map(|x| !x)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        if self.is_output() {
+            return Err(PinModeError::IncorrectMode);
+        }
+        Ok(self.gpio().pdir.read().bits() & (1 << self.i) == 0)
+    }
+}
+
+impl OutputPin for Pin<Output<PushPull>> {
+    type Error = crate::NoError;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe { self.gpio().psor.write(|w| w.bits(1 << self.i)) }
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe { self.gpio().pcor.write(|w| w.bits(1 << self.i)) }
+        Ok(())
+    }
+}
+
+impl StatefulOutputPin for Pin<Output<PushPull>> {
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        self.is_set_low().map(|x| !x)
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.gpio().pdor.read().bits() & (1 << self.i) == 0)
+    }
+}
+
+impl ToggleableOutputPin for Pin<Output<PushPull>> {
+    type Error = crate::NoError;
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe { self.gpio().ptor.write(|w| w.bits(1 << self.i)) }
+        Ok(())
+    }
+}
+
+impl OutputPin for Pin<Output<OpenDrain>> {
+    type Error = crate::NoError;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        // Release the line by switching the pin to input.
+        cortex_m::interrupt::free(|_| unsafe {
+            self.gpio()
+                .pddr
+                .modify(|r, w| w.pdd().bits(r.pdd().bits() & !(1 << self.i)))
+        });
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        // PDOR is held low, so the line is pulled low when the pin is an output.
+        cortex_m::interrupt::free(|_| unsafe {
+            self.gpio()
+                .pddr
+                .modify(|r, w| w.pdd().bits(r.pdd().bits() | (1 << self.i)))
+        });
+        Ok(())
+    }
+}
+
+impl StatefulOutputPin for Pin<Output<OpenDrain>> {
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        self.is_set_low().map(|x| !x)
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.gpio().pddr.read().bits() & (1 << self.i) != 0)
+    }
+}
+
+impl toggleable::Default for Pin<Output<OpenDrain>> {}
+
+impl InputPin for Pin<Output<OpenDrain>> {
+    type Error = crate::NoError;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.is_low().map(|x| !x)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.gpio().pdir.read().bits() & (1 << self.i) == 0)
+    }
+}
+
+impl<MODE> InputPin for Pin<Input<MODE>> {
+    type Error = crate::NoError;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.is_low().map(|x| !x)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.gpio().pdir.read().bits() & (1 << self.i) == 0)
+    }
+}
+
+macro_rules! exti {
+    ($PORTX:ident, $PXx:ident, $port:expr, [$($PXi:ident: $i:expr,)+]) => {
+        use super::{DmaPin, DmaSource, DmaTrigger, Edge, ExtiPin};
+
+        // DMAMUX sources 49 to 52 are assigned to ports A to D.
+        const DMA_SOURCE: DmaSource = DmaSource { source: 49 + $port };
+
+        /// Returns the interrupt status flags of all pins of the port
+        pub fn interrupt_status() -> u32 {
+            // NOTE(unsafe) atomic read with no side effects
+            unsafe { (*$PORTX::ptr()).isfr.read().bits() }
+        }
+
+        /// Clears the interrupt status flags of the pins selected by `mask`
+        pub fn clear_interrupt_status(mask: u32) {
+            // NOTE(unsafe) atomic write, the flags are cleared by writing 1
+            unsafe { (*$PORTX::ptr()).isfr.write(|w| w.bits(mask)) }
+        }
+
+        impl<MODE> ExtiPin for $PXx<Input<MODE>> {
+            fn enable_interrupt(&mut self, edge: Edge) {
+                // NOTE(unsafe) this pin is the only owner of its PCR
+                unsafe {
+                    (*$PORTX::ptr()).pcr[self.i as usize]
+                        .modify(|_, w| w.isf().clear_bit().irqc().bits(edge.irqc()));
+                }
+            }
+
+            fn disable_interrupt(&mut self) {
+                // NOTE(unsafe) this pin is the only owner of its PCR
+                unsafe {
+                    (*$PORTX::ptr()).pcr[self.i as usize]
+                        .modify(|_, w| w.isf().clear_bit().irqc()._0000());
+                }
+            }
+
+            fn check_interrupt(&self) -> bool {
+                interrupt_status() & (1 << self.i) != 0
+            }
+
+            fn clear_interrupt_pending_bit(&mut self) {
+                clear_interrupt_status(1 << self.i);
+            }
+        }
+
+        impl<MODE> DmaPin for $PXx<Input<MODE>> {
+            fn enable_dma_request(&mut self, trigger: DmaTrigger) -> DmaSource {
+                // NOTE(unsafe) this pin is the only owner of its PCR
+                unsafe {
+                    (*$PORTX::ptr()).pcr[self.i as usize]
+                        .modify(|_, w| w.isf().clear_bit().irqc().bits(trigger.irqc()));
+                }
+                DMA_SOURCE
+            }
+
+            fn disable_dma_request(&mut self) {
+                // NOTE(unsafe) this pin is the only owner of its PCR
+                unsafe {
+                    (*$PORTX::ptr()).pcr[self.i as usize]
+                        .modify(|_, w| w.isf().clear_bit().irqc()._0000());
+                }
+            }
+        }
+
+        $(
+            impl<MODE> ExtiPin for $PXi<Input<MODE>> {
+                fn enable_interrupt(&mut self, edge: Edge) {
+                    // NOTE(unsafe) this pin is the only owner of its PCR
+                    unsafe {
+                        (*$PORTX::ptr()).pcr[$i]
+                            .modify(|_, w| w.isf().clear_bit().irqc().bits(edge.irqc()));
+                    }
+                }
+
+                fn disable_interrupt(&mut self) {
+                    // NOTE(unsafe) this pin is the only owner of its PCR
+                    unsafe {
+                        (*$PORTX::ptr()).pcr[$i]
+                            .modify(|_, w| w.isf().clear_bit().irqc()._0000());
+                    }
+                }
+
+                fn check_interrupt(&self) -> bool {
+                    interrupt_status() & (1 << $i) != 0
+                }
+
+                fn clear_interrupt_pending_bit(&mut self) {
+                    clear_interrupt_status(1 << $i);
+                }
+            }
+
+            impl<MODE> DmaPin for $PXi<Input<MODE>> {
+                fn enable_dma_request(&mut self, trigger: DmaTrigger) -> DmaSource {
+                    // NOTE(unsafe) this pin is the only owner of its PCR
+                    unsafe {
+                        (*$PORTX::ptr()).pcr[$i]
+                            .modify(|_, w| w.isf().clear_bit().irqc().bits(trigger.irqc()));
+                    }
+                    DMA_SOURCE
+                }
+
+                fn disable_dma_request(&mut self) {
+                    // NOTE(unsafe) this pin is the only owner of its PCR
+                    unsafe {
+                        (*$PORTX::ptr()).pcr[$i]
+                            .modify(|_, w| w.isf().clear_bit().irqc()._0000());
+                    }
+                }
+            }
+        )+
+    }
+}
+
+macro_rules! no_exti {
+    ($PORTX:ident, $PXx:ident, $port:expr, [$($PXi:ident: $i:expr,)+]) => {};
+}
+
 macro_rules! gpio {
-    ($GPIOX:ident, $gpiox:ident, $PORTX:ident, $portx:ident, $PXx:ident, [
+    ($GPIOX:ident, $gpiox:ident, $FGPIOX:ident, $PORTX:ident, $portx:ident, $PXx:ident, $port:expr, $exti:ident, [
         $($PXi:ident: ($pxi:ident, $i:expr, $MODE:ty),)+
     ]) => {
         /// GPIO
         pub mod $gpiox {
             use core::marker::PhantomData;
 
-            use super::super::hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin, toggleable};
-            use mkl25z4::{$PORTX, $GPIOX, $gpiox, SIM};
+            use super::super::hal::digital::v2::{
+                toggleable, InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin,
+            };
+            use mkl25z4::{$PORTX, $GPIOX, $FGPIOX, SIM};
 
             use super::{
-                Alternate, Floating, GpioExt, Input,
-                // OpenDrain,
+                sealed, Alternate, Dynamic, SlewRate, Fast, Floating, GpioExt, GroupOutputPin, GroupPin, Input, Pin,
+                OpenDrain,
                 Output,
-                PullDown, PullUp,
+                PinModeError, PinState, Pull, PullDown, PullUp,
                 PushPull,
             };
 
             /// GPIO parts
             pub struct Parts {
-                pub pddr: PDDR,
                 $(
                     /// Pin
                     pub $pxi: $PXi<$MODE>,
@@ -139,7 +766,6 @@ macro_rules! gpio {
                     sim.scgc5.modify(|_, w| w.$portx().set_bit());
 
                     Parts {
-                        pddr: PDDR { _0: () },
                         $(
                             $pxi: $PXi { _mode: PhantomData },
                         )+
@@ -147,24 +773,67 @@ macro_rules! gpio {
                 }
             }
 
-            /// Opaque PDDR register
-            pub struct PDDR {
-                _0: (),
+            /// Configures the direction of a pin of this port
+            fn set_direction(i: u8, output: bool) {
+                cortex_m::interrupt::free(|_| unsafe {
+                    (*$GPIOX::ptr()).pddr.modify(|r, w| {
+                        if output {
+                            w.pdd().bits(r.pdd().bits() | (1 << i))
+                        } else {
+                            w.pdd().bits(r.pdd().bits() & !(1 << i))
+                        }
+                    })
+                });
             }
 
-            impl PDDR {
-                pub(crate) fn pddr(&mut self) -> &$gpiox::PDDR {
-                    unsafe { &(*$GPIOX::ptr()).pddr }
+            impl sealed::Sealed for $GPIOX {}
+
+            impl sealed::Port for $GPIOX {
+                fn set(mask: u32) {
+                    // NOTE(unsafe) atomic write to a stateless register
+                    unsafe { (*$GPIOX::ptr()).psor.write(|w| w.bits(mask)) }
+                }
+
+                fn clear(mask: u32) {
+                    // NOTE(unsafe) atomic write to a stateless register
+                    unsafe { (*$GPIOX::ptr()).pcor.write(|w| w.bits(mask)) }
+                }
+
+                fn input() -> u32 {
+                    // NOTE(unsafe) atomic read with no side effects
+                    unsafe { (*$GPIOX::ptr()).pdir.read().bits() }
                 }
             }
 
+            /// Toggles the outputs of all pins selected by `mask` at once
+            ///
+            /// Only push pull outputs should be selected, the output latch of open drain pins has to
+            /// stay low.
+            pub fn toggle_mask(mask: u32) {
+                // NOTE(unsafe) atomic write to a stateless register
+                unsafe { (*$GPIOX::ptr()).ptor.write(|w| w.bits(mask)) }
+            }
+
             /// Partially erased pin
             pub struct $PXx<MODE> {
                 i: u8,
                 _mode: PhantomData<MODE>,
             }
 
-            impl<MODE> OutputPin for $PXx<Output<MODE>> {
+            impl<MODE> $PXx<MODE> {
+                /// Erases the port from the type
+                ///
+                /// This is useful when you want to collect pins of different ports into an array
+                pub fn erase(self) -> Pin<MODE> {
+                    Pin {
+                        port: $port,
+                        i: self.i,
+                        _mode: self._mode,
+                    }
+                }
+            }
+
+            impl OutputPin for $PXx<Output<PushPull>> {
                 type Error = crate::NoError;
 
                 fn set_high(&mut self) -> Result<(), Self::Error> {
@@ -180,7 +849,7 @@ macro_rules! gpio {
                 }
             }
 
-            impl <MODE> StatefulOutputPin for $PXx<Output<MODE>> {
+            impl StatefulOutputPin for $PXx<Output<PushPull>> {
                 fn is_set_high(&self) -> Result<bool, Self::Error> {
                     self.is_set_low().map(|x| !x)
                 }
@@ -191,7 +860,138 @@ macro_rules! gpio {
                 }
             }
 
-            impl <MODE> toggleable::Default for $PXx<Output<MODE>> {}
+            impl ToggleableOutputPin for $PXx<Output<PushPull>> {
+                type Error = crate::NoError;
+
+                fn toggle(&mut self) -> Result<(), Self::Error> {
+                    // NOTE(unsafe) atomic write to a stateless register
+                    unsafe { (*$GPIOX::ptr()).ptor.write(|w| w.bits(1 << self.i)) }
+                    Ok(())
+                }
+            }
+
+            impl<MODE> InputPin for $PXx<Input<MODE>> {
+                type Error = crate::NoError;
+
+                fn is_high(&self) -> Result<bool, Self::Error> {
+                    self.is_low().map(|x| !x)
+                }
+
+                fn is_low(&self) -> Result<bool, Self::Error> {
+                    // NOTE(unsafe) atomic read with no side effects
+                    Ok(unsafe { (*$GPIOX::ptr()).pdir.read().bits() & (1 << self.i) == 0 })
+                }
+            }
+
+            impl<MODE> $PXx<Input<MODE>> {
+                /// Routes accesses to the pin through the single-cycle IOPORT interface
+                pub fn into_fast(self) -> Fast<Self> {
+                    Fast { pin: self }
+                }
+            }
+
+            impl<MODE> $PXx<Output<MODE>> {
+                /// Routes accesses to the pin through the single-cycle IOPORT interface
+                pub fn into_fast(self) -> Fast<Self> {
+                    Fast { pin: self }
+                }
+            }
+
+            impl OutputPin for Fast<$PXx<Output<PushPull>>> {
+                type Error = crate::NoError;
+
+                fn set_high(&mut self) -> Result<(), Self::Error> {
+                    // NOTE(unsafe) atomic write to a stateless register
+                    unsafe { (*$FGPIOX::ptr()).psor.write(|w| w.bits(1 << self.pin.i)) }
+                    Ok(())
+                }
+
+                fn set_low(&mut self) -> Result<(), Self::Error> {
+                    // NOTE(unsafe) atomic write to a stateless register
+                    unsafe { (*$FGPIOX::ptr()).pcor.write(|w| w.bits(1 << self.pin.i)) }
+                    Ok(())
+                }
+            }
+
+            impl StatefulOutputPin for Fast<$PXx<Output<PushPull>>> {
+                fn is_set_high(&self) -> Result<bool, Self::Error> {
+                    self.is_set_low().map(|x| !x)
+                }
+
+                fn is_set_low(&self) -> Result<bool, Self::Error> {
+                    // NOTE(unsafe) atomic read with no side effects
+                    Ok(unsafe { (*$FGPIOX::ptr()).pdor.read().bits() & (1 << self.pin.i) == 0 })
+                }
+            }
+
+            impl ToggleableOutputPin for Fast<$PXx<Output<PushPull>>> {
+                type Error = crate::NoError;
+
+                fn toggle(&mut self) -> Result<(), Self::Error> {
+                    // NOTE(unsafe) atomic write to a stateless register
+                    unsafe { (*$FGPIOX::ptr()).ptor.write(|w| w.bits(1 << self.pin.i)) }
+                    Ok(())
+                }
+            }
+
+            impl<MODE> InputPin for Fast<$PXx<Input<MODE>>> {
+                type Error = crate::NoError;
+
+                fn is_high(&self) -> Result<bool, Self::Error> {
+                    self.is_low().map(|x| !x)
+                }
+
+                fn is_low(&self) -> Result<bool, Self::Error> {
+                    // NOTE(unsafe) atomic read with no side effects
+                    Ok(unsafe { (*$FGPIOX::ptr()).pdir.read().bits() & (1 << self.pin.i) == 0 })
+                }
+            }
+
+            impl OutputPin for $PXx<Output<OpenDrain>> {
+                type Error = crate::NoError;
+
+                fn set_high(&mut self) -> Result<(), Self::Error> {
+                    // Release the line by switching the pin to input.
+                    cortex_m::interrupt::free(|_| unsafe {
+                        (*$GPIOX::ptr()).pddr.modify(|r, w| w.pdd().bits(r.pdd().bits() & !(1 << self.i)))
+                    });
+                    Ok(())
+                }
+
+                fn set_low(&mut self) -> Result<(), Self::Error> {
+                    // PDOR is held low, so the line is pulled low when the pin is an output.
+                    cortex_m::interrupt::free(|_| unsafe {
+                        (*$GPIOX::ptr()).pddr.modify(|r, w| w.pdd().bits(r.pdd().bits() | (1 << self.i)))
+                    });
+                    Ok(())
+                }
+            }
+
+            impl StatefulOutputPin for $PXx<Output<OpenDrain>> {
+                fn is_set_high(&self) -> Result<bool, Self::Error> {
+                    self.is_set_low().map(|x| !x)
+                }
+
+                fn is_set_low(&self) -> Result<bool, Self::Error> {
+                    // NOTE(unsafe) atomic read with no side effects
+                    Ok(unsafe { (*$GPIOX::ptr()).pddr.read().bits() & (1 << self.i) != 0 })
+                }
+            }
+
+            impl toggleable::Default for $PXx<Output<OpenDrain>> {}
+
+            impl InputPin for $PXx<Output<OpenDrain>> {
+                type Error = crate::NoError;
+
+                fn is_high(&self) -> Result<bool, Self::Error> {
+                    self.is_low().map(|x| !x)
+                }
+
+                fn is_low(&self) -> Result<bool, Self::Error> {
+                    // NOTE(unsafe) atomic read with no side effects
+                    Ok(unsafe { (*$GPIOX::ptr()).pdir.read().bits() & (1 << self.i) == 0 })
+                }
+            }
 
             $(
                 /// Pin
@@ -200,21 +1000,45 @@ macro_rules! gpio {
                 }
 
                 impl<MODE> $PXi<MODE> {
+                    /// Sets the slew rate of the pin
+                    ///
+                    /// The setting is kept when the mode of the pin is changed.
+                    pub fn set_slew_rate(&mut self, slew_rate: SlewRate) {
+                        // NOTE(unsafe) this pin is the only owner of its PCR
+                        unsafe {
+                            (*$PORTX::ptr()).pcr[$i].modify(|_, w| {
+                                w.isf().clear_bit().sre().bit(slew_rate == SlewRate::Slow)
+                            });
+                        }
+                    }
+
+                    /// Enables or disables the passive input filter of the pin
+                    ///
+                    /// The filter suppresses glitches, but must not be used for signals faster than
+                    /// 2 MHz. The setting is kept when the mode of the pin is changed.
+                    pub fn set_passive_filter(&mut self, enabled: bool) {
+                        // NOTE(unsafe) this pin is the only owner of its PCR
+                        unsafe {
+                            (*$PORTX::ptr()).pcr[$i].modify(|_, w| w.isf().clear_bit().pfe().bit(enabled));
+                        }
+                    }
+
                     /// Configures the pin to operate as an alternate function pin
                     pub fn into_alternate<ALTERNATE>(
                         self,
-                        pddr: &mut PDDR,
                     ) -> $PXi<ALTERNATE>
                     where
                         ALTERNATE: Alternate,
                     {
                         unsafe {
                             // Configure GPIO as input.
-                            pddr.pddr().modify(|r, w| w.pdd().bits(r.pdd().bits() & !(1 << $i)));
+                            set_direction($i, false);
                             // Configure pin as alternate.
-                            (*$PORTX::ptr()).pcr[$i].write(|w| w.bits(0)
-                                                           .mux().bits(ALTERNATE::MUX) // GPIO
-                                                           .dse().set_bit() // High drive strength
+                            (*$PORTX::ptr()).pcr[$i].modify(|_, w| w
+                                                           .mux().bits(ALTERNATE::MUX) // Alternate function
+                                                           .pe().clear_bit() // No pullup/pulldown
+                                                           .isf().clear_bit()
+                                                           .irqc()._0000()
                                                            );
                         }
 
@@ -224,15 +1048,16 @@ macro_rules! gpio {
                     /// Configures the pin to operate as a floating input pin
                     pub fn into_floating_input(
                         self,
-                        pddr: &mut PDDR,
                     ) -> $PXi<Input<Floating>> {
                         unsafe {
                             // Configure GPIO as input.
-                            pddr.pddr().modify(|r, w| w.pdd().bits(r.pdd().bits() & !(1 << $i)));
+                            set_direction($i, false);
                             // Configure pin (no pullup/pulldown configured).
-                            (*$PORTX::ptr()).pcr[$i].write(|w| w.bits(0)
+                            (*$PORTX::ptr()).pcr[$i].modify(|_, w| w
                                                            .mux()._001() // GPIO
-                                                           .dse().set_bit() // High drive strength
+                                                           .pe().clear_bit() // No pullup/pulldown
+                                                           .isf().clear_bit()
+                                                           .irqc()._0000()
                                                            );
                         }
 
@@ -242,17 +1067,17 @@ macro_rules! gpio {
                     /// Configures the pin to operate as a pull-up input pin
                     pub fn into_pull_up_input(
                         self,
-                        pddr: &mut PDDR,
                     ) -> $PXi<Input<PullUp>> {
                         unsafe {
                             // Configure GPIO as input.
-                            pddr.pddr().modify(|r, w| w.pdd().bits(r.pdd().bits() & !(1 << $i)));
+                            set_direction($i, false);
                             // Configure pin (enable pullup).
-                            (*$PORTX::ptr()).pcr[$i].write(|w| w.bits(0)
+                            (*$PORTX::ptr()).pcr[$i].modify(|_, w| w
                                                            .mux()._001() // GPIO
-                                                           .dse().set_bit() // High drive strength
                                                            .pe().set_bit() // Pull enable
                                                            .ps().set_bit() // Pullup
+                                                           .isf().clear_bit()
+                                                           .irqc()._0000()
                                                            );
                         }
 
@@ -262,17 +1087,17 @@ macro_rules! gpio {
                     /// Configures the pin to operate as a pull-down input pin
                     pub fn into_pull_down_input(
                         self,
-                        pddr: &mut PDDR,
                     ) -> $PXi<Input<PullDown>> {
                         unsafe {
                             // Configure GPIO as input.
-                            pddr.pddr().modify(|r, w| w.pdd().bits(r.pdd().bits() & !(1 << $i)));
+                            set_direction($i, false);
                             // Configure pin (enable pulldown).
-                            (*$PORTX::ptr()).pcr[$i].write(|w| w.bits(0)
+                            (*$PORTX::ptr()).pcr[$i].modify(|_, w| w
                                                            .mux()._001() // GPIO
-                                                           .dse().set_bit() // High drive strength
                                                            .pe().set_bit() // Pull enable
                                                            .ps().clear_bit() // Pulldown
+                                                           .isf().clear_bit()
+                                                           .irqc()._0000()
                                                            );
                         }
 
@@ -282,20 +1107,146 @@ macro_rules! gpio {
                     /// Configures the pin to operate as an push pull output pin
                     pub fn into_push_pull_output(
                         self,
-                        pddr: &mut PDDR,
                     ) -> $PXi<Output<PushPull>> {
                         unsafe {
                             // Configure GPIO as output.
-                            pddr.pddr().modify(|r, w| w.pdd().bits(r.pdd().bits() | (1 << $i)));
+                            set_direction($i, true);
                             // Configure pin.
-                            (*$PORTX::ptr()).pcr[$i].write(|w| w.bits(0)
+                            (*$PORTX::ptr()).pcr[$i].modify(|_, w| w
                                                            .mux()._001() // GPIO
-                                                           .dse().set_bit() // High drive strength
+                                                           .pe().clear_bit() // No pullup/pulldown
+                                                           .isf().clear_bit()
+                                                           .irqc()._0000()
                                                            );
                         }
 
                         $PXi { _mode: PhantomData }
                     }
+
+                    /// Configures the pin to operate as a push pull output pin with the specified
+                    /// initial state
+                    ///
+                    /// The output latch is written before the pin is switched to output, so the
+                    /// pin does not glitch.
+                    pub fn into_push_pull_output_with_state(
+                        self,
+                        initial_state: PinState,
+                    ) -> $PXi<Output<PushPull>> {
+                        // NOTE(unsafe) atomic write to a stateless register
+                        unsafe {
+                            match initial_state {
+                                PinState::High => (*$GPIOX::ptr()).psor.write(|w| w.bits(1 << $i)),
+                                PinState::Low => (*$GPIOX::ptr()).pcor.write(|w| w.bits(1 << $i)),
+                            }
+                        }
+
+                        self.into_push_pull_output()
+                    }
+
+                    /// Configures the pin to operate as an emulated open drain output pin
+                    ///
+                    /// The output latch is held low and the line is driven by switching the pin
+                    /// between input (high, released) and output (low). The line needs an
+                    /// external pull-up resistor.
+                    pub fn into_open_drain_output(
+                        self,
+                    ) -> $PXi<Output<OpenDrain>> {
+                        self.into_open_drain_output_with_state(PinState::High)
+                    }
+
+                    /// Configures the pin to operate as an emulated open drain output pin with
+                    /// the specified initial state
+                    pub fn into_open_drain_output_with_state(
+                        self,
+                        initial_state: PinState,
+                    ) -> $PXi<Output<OpenDrain>> {
+                        unsafe {
+                            // Release the line before clearing the output latch, or clear the
+                            // latch before pulling the line down, so the line never glitches low.
+                            match initial_state {
+                                PinState::High => {
+                                    set_direction($i, false);
+                                    (*$GPIOX::ptr()).pcor.write(|w| w.bits(1 << $i));
+                                }
+                                PinState::Low => {
+                                    (*$GPIOX::ptr()).pcor.write(|w| w.bits(1 << $i));
+                                    set_direction($i, true);
+                                }
+                            }
+                            // Configure pin.
+                            (*$PORTX::ptr()).pcr[$i].modify(|_, w| w
+                                                           .mux()._001() // GPIO
+                                                           .pe().clear_bit() // No pullup/pulldown
+                                                           .isf().clear_bit()
+                                                           .irqc()._0000()
+                                                           );
+                        }
+
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to be switchable between input and output at runtime
+                    ///
+                    /// The pin starts as floating input.
+                    pub fn into_dynamic(self) -> $PXi<Dynamic> {
+                        self.into_floating_input();
+
+                        $PXi { _mode: PhantomData }
+                    }
+                }
+
+                impl $PXi<Dynamic> {
+                    fn dynamic(&self) -> Pin<Dynamic> {
+                        Pin {
+                            port: $port,
+                            i: $i,
+                            _mode: PhantomData,
+                        }
+                    }
+
+                    /// Configures the pin as input with the specified pull resistor
+                    pub fn make_input(&mut self, pull: Pull) {
+                        self.dynamic().make_input(pull)
+                    }
+
+                    /// Configures the pin as push pull output with the specified initial state
+                    pub fn make_output(&mut self, initial_state: PinState) {
+                        self.dynamic().make_output(initial_state)
+                    }
+
+                    /// Returns whether the pin is currently configured as output
+                    pub fn is_output(&self) -> bool {
+                        self.dynamic().is_output()
+                    }
+
+                    /// Erases the port and the pin number from the type
+                    pub fn erase(self) -> Pin<Dynamic> {
+                        self.dynamic()
+                    }
+                }
+
+                impl OutputPin for $PXi<Dynamic> {
+                    type Error = PinModeError;
+
+                    fn set_high(&mut self) -> Result<(), Self::Error> {
+                        self.dynamic().set_high()
+                    }
+
+                    fn set_low(&mut self) -> Result<(), Self::Error> {
+                        self.dynamic().set_low()
+                    }
+                }
+
+                impl InputPin for $PXi<Dynamic> {
+                    type Error = PinModeError;
+
+                    fn is_high(&self) -> Result<bool, Self::Error> {
+                        self.dynamic().is_high()
+                    }
+
+                    fn is_low(&self) -> Result<bool, Self::Error> {
+                        self.dynamic().is_low()
+                    }
                 }
 
                 impl<MODE> $PXi<Output<MODE>> {
@@ -309,9 +1260,32 @@ macro_rules! gpio {
                             _mode: self._mode,
                         }
                     }
+
+                    /// Erases the port and the pin number from the type
+                    pub fn erase(self) -> Pin<Output<MODE>> {
+                        self.downgrade().erase()
+                    }
                 }
 
-                impl<MODE> OutputPin for $PXi<Output<MODE>> {
+                impl<MODE> $PXi<Input<MODE>> {
+                    /// Erases the pin number from the type
+                    ///
+                    /// This is useful when you want to collect the pins into an array where you
+                    /// need all the elements to have the same type
+                    pub fn downgrade(self) -> $PXx<Input<MODE>> {
+                        $PXx {
+                            i: $i,
+                            _mode: self._mode,
+                        }
+                    }
+
+                    /// Erases the port and the pin number from the type
+                    pub fn erase(self) -> Pin<Input<MODE>> {
+                        self.downgrade().erase()
+                    }
+                }
+
+                impl OutputPin for $PXi<Output<PushPull>> {
                     type Error = crate::NoError;
 
                     fn set_high(&mut self) -> Result<(), Self::Error> {
@@ -327,7 +1301,7 @@ macro_rules! gpio {
                     }
                 }
 
-                impl<MODE> StatefulOutputPin for $PXi<Output<MODE>> {
+                impl StatefulOutputPin for $PXi<Output<PushPull>> {
                     fn is_set_high(&self) -> Result<bool, Self::Error> {
                         self.is_set_low().map(|x| !x)
                     }
@@ -338,7 +1312,139 @@ macro_rules! gpio {
                     }
                 }
 
-                impl <MODE> toggleable::Default for $PXi<Output<MODE>> {}
+                impl ToggleableOutputPin for $PXi<Output<PushPull>> {
+                    type Error = crate::NoError;
+
+                    fn toggle(&mut self) -> Result<(), Self::Error> {
+                        // NOTE(unsafe) atomic write to a stateless register
+                        unsafe { (*$GPIOX::ptr()).ptor.write(|w| w.bits(1 << $i)) }
+                        Ok(())
+                    }
+                }
+
+                impl<MODE> $PXi<Input<MODE>> {
+                    /// Routes accesses to the pin through the single-cycle IOPORT interface
+                    pub fn into_fast(self) -> Fast<Self> {
+                        Fast { pin: self }
+                    }
+                }
+
+                impl<MODE> $PXi<Output<MODE>> {
+                    /// Routes accesses to the pin through the single-cycle IOPORT interface
+                    pub fn into_fast(self) -> Fast<Self> {
+                        Fast { pin: self }
+                    }
+                }
+
+                impl OutputPin for Fast<$PXi<Output<PushPull>>> {
+                    type Error = crate::NoError;
+
+                    fn set_high(&mut self) -> Result<(), Self::Error> {
+                        // NOTE(unsafe) atomic write to a stateless register
+                        unsafe { (*$FGPIOX::ptr()).psor.write(|w| w.bits(1 << $i)) }
+                        Ok(())
+                    }
+
+                    fn set_low(&mut self) -> Result<(), Self::Error> {
+                        // NOTE(unsafe) atomic write to a stateless register
+                        unsafe { (*$FGPIOX::ptr()).pcor.write(|w| w.bits(1 << $i)) }
+                        Ok(())
+                    }
+                }
+
+                impl StatefulOutputPin for Fast<$PXi<Output<PushPull>>> {
+                    fn is_set_high(&self) -> Result<bool, Self::Error> {
+                        self.is_set_low().map(|x| !x)
+                    }
+
+                    fn is_set_low(&self) -> Result<bool, Self::Error> {
+                        // NOTE(unsafe) atomic read with no side effects
+                        Ok(unsafe { (*$FGPIOX::ptr()).pdor.read().bits() & (1 << $i) == 0 })
+                    }
+                }
+
+                impl ToggleableOutputPin for Fast<$PXi<Output<PushPull>>> {
+                    type Error = crate::NoError;
+
+                    fn toggle(&mut self) -> Result<(), Self::Error> {
+                        // NOTE(unsafe) atomic write to a stateless register
+                        unsafe { (*$FGPIOX::ptr()).ptor.write(|w| w.bits(1 << $i)) }
+                        Ok(())
+                    }
+                }
+
+                impl<MODE> InputPin for Fast<$PXi<Input<MODE>>> {
+                    type Error = crate::NoError;
+
+                    fn is_high(&self) -> Result<bool, Self::Error> {
+                        self.is_low().map(|x| !x)
+                    }
+
+                    fn is_low(&self) -> Result<bool, Self::Error> {
+                        // NOTE(unsafe) atomic read with no side effects
+                        Ok(unsafe { (*$FGPIOX::ptr()).pdir.read().bits() & (1 << $i) == 0 })
+                    }
+                }
+
+                impl OutputPin for $PXi<Output<OpenDrain>> {
+                    type Error = crate::NoError;
+
+                    fn set_high(&mut self) -> Result<(), Self::Error> {
+                        // Release the line by switching the pin to input.
+                        cortex_m::interrupt::free(|_| unsafe {
+                            (*$GPIOX::ptr()).pddr.modify(|r, w| w.pdd().bits(r.pdd().bits() & !(1 << $i)))
+                        });
+                        Ok(())
+                    }
+
+                    fn set_low(&mut self) -> Result<(), Self::Error> {
+                        // PDOR is held low, so the line is pulled low when the pin is an output.
+                        cortex_m::interrupt::free(|_| unsafe {
+                            (*$GPIOX::ptr()).pddr.modify(|r, w| w.pdd().bits(r.pdd().bits() | (1 << $i)))
+                        });
+                        Ok(())
+                    }
+                }
+
+                impl StatefulOutputPin for $PXi<Output<OpenDrain>> {
+                    fn is_set_high(&self) -> Result<bool, Self::Error> {
+                        self.is_set_low().map(|x| !x)
+                    }
+
+                    fn is_set_low(&self) -> Result<bool, Self::Error> {
+                        // NOTE(unsafe) atomic read with no side effects
+                        Ok(unsafe { (*$GPIOX::ptr()).pddr.read().bits() & (1 << $i) != 0 })
+                    }
+                }
+
+                impl toggleable::Default for $PXi<Output<OpenDrain>> {}
+
+                impl InputPin for $PXi<Output<OpenDrain>> {
+                    type Error = crate::NoError;
+
+                    fn is_high(&self) -> Result<bool, Self::Error> {
+                        self.is_low().map(|x| !x)
+                    }
+
+                    fn is_low(&self) -> Result<bool, Self::Error> {
+                        // NOTE(unsafe) atomic read with no side effects
+                        Ok(unsafe { (*$GPIOX::ptr()).pdir.read().bits() & (1 << $i) == 0 })
+                    }
+                }
+
+                impl<MODE> sealed::Sealed for $PXi<MODE> {}
+
+                impl<MODE> GroupPin for $PXi<Input<MODE>> {
+                    type Port = $GPIOX;
+                    const MASK: u32 = 1 << $i;
+                }
+
+                impl GroupPin for $PXi<Output<PushPull>> {
+                    type Port = $GPIOX;
+                    const MASK: u32 = 1 << $i;
+                }
+
+                impl GroupOutputPin for $PXi<Output<PushPull>> {}
 
                 impl<MODE> InputPin for $PXi<Input<MODE>> {
                     type Error = crate::NoError;
@@ -353,11 +1459,13 @@ macro_rules! gpio {
                     }
                 }
             )+
+
+            $exti!($PORTX, $PXx, $port, [$($PXi: $i,)+]);
         }
     }
 }
 
-gpio!(GPIOA, gpioa, PORTA, porta, PAx, [
+gpio!(GPIOA, gpioa, FGPIOA, PORTA, porta, PAx, 0, exti, [
     PA0: (pa0, 0, Input<Floating>),
     PA1: (pa1, 1, Input<Floating>),
     PA2: (pa2, 2, Input<Floating>),
@@ -392,7 +1500,7 @@ gpio!(GPIOA, gpioa, PORTA, porta, PAx, [
     PA31: (pa31, 31, Input<Floating>),
 ]);
 
-gpio!(GPIOB, gpiob, PORTB, portb, PBx, [
+gpio!(GPIOB, gpiob, FGPIOB, PORTB, portb, PBx, 1, no_exti, [
     PB0: (pb0, 0, Input<Floating>),
     PB1: (pb1, 1, Input<Floating>),
     PB2: (pb2, 2, Input<Floating>),
@@ -427,7 +1535,7 @@ gpio!(GPIOB, gpiob, PORTB, portb, PBx, [
     PB31: (pb31, 31, Input<Floating>),
 ]);
 
-gpio!(GPIOC, gpioc, PORTC, portc, PCx, [
+gpio!(GPIOC, gpioc, FGPIOC, PORTC, portc, PCx, 2, no_exti, [
     PC0: (pc0, 0, Input<Floating>),
     PC1: (pc1, 1, Input<Floating>),
     PC2: (pc2, 2, Input<Floating>),
@@ -462,7 +1570,7 @@ gpio!(GPIOC, gpioc, PORTC, portc, PCx, [
     PC31: (pc31, 31, Input<Floating>),
 ]);
 
-gpio!(GPIOD, gpiod, PORTD, portd, PDx, [
+gpio!(GPIOD, gpiod, FGPIOD, PORTD, portd, PDx, 3, exti, [
     PD0: (pd0, 0, Input<Floating>),
     PD1: (pd1, 1, Input<Floating>),
     PD2: (pd2, 2, Input<Floating>),
@@ -497,7 +1605,7 @@ gpio!(GPIOD, gpiod, PORTD, portd, PDx, [
     PD31: (pd31, 31, Input<Floating>),
 ]);
 
-gpio!(GPIOE, gpioe, PORTE, porte, PEx, [
+gpio!(GPIOE, gpioe, FGPIOE, PORTE, porte, PEx, 4, no_exti, [
     PE0: (pe0, 0, Input<Floating>),
     PE1: (pe1, 1, Input<Floating>),
     PE2: (pe2, 2, Input<Floating>),
@@ -531,3 +1639,100 @@ gpio!(GPIOE, gpioe, PORTE, porte, PEx, [
     PE30: (pe30, 30, Input<Floating>),
     PE31: (pe31, 31, Input<Floating>),
 ]);
+
+/// Pins of all GPIO ports
+pub struct Pins {
+    /// Pins of port A
+    pub gpioa: gpioa::Parts,
+    /// Pins of port B
+    pub gpiob: gpiob::Parts,
+    /// Pins of port C
+    pub gpioc: gpioc::Parts,
+    /// Pins of port D
+    pub gpiod: gpiod::Parts,
+    /// Pins of port E
+    pub gpioe: gpioe::Parts,
+}
+
+impl Pins {
+    /// Enables the clocks of all ports and splits them into independent pins
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        gpioa: GPIOA,
+        gpiob: GPIOB,
+        gpioc: GPIOC,
+        gpiod: GPIOD,
+        gpioe: GPIOE,
+        _porta: PORTA,
+        _portb: PORTB,
+        _portc: PORTC,
+        _portd: PORTD,
+        _porte: PORTE,
+        sim: &mut SIM,
+    ) -> Self {
+        Pins {
+            gpioa: gpioa.split(sim),
+            gpiob: gpiob.split(sim),
+            gpioc: gpioc.split(sim),
+            gpiod: gpiod.split(sim),
+            gpioe: gpioe.split(sim),
+        }
+    }
+}
+
+macro_rules! high_drive {
+    ($PORTX:ident, [$($gpiox:ident::$PXi:ident: $i:expr,)+]) => {
+        $(
+            impl<MODE> $gpiox::$PXi<MODE> {
+                /// Sets the drive strength of the pin
+                ///
+                /// Only the pins PTB0, PTB1, PTC3, PTC4, PTD6 and PTD7 support high drive strength.
+                /// The setting is kept when the mode of the pin is changed.
+                pub fn set_drive_strength(&mut self, strength: DriveStrength) {
+                    // NOTE(unsafe) this pin is the only owner of its PCR
+                    unsafe {
+                        (*mkl25z4::$PORTX::ptr()).pcr[$i].modify(|_, w| {
+                            w.isf().clear_bit().dse().bit(strength == DriveStrength::High)
+                        });
+                    }
+                }
+            }
+        )+
+    };
+}
+
+high_drive!(PORTB, [gpiob::PB0: 0, gpiob::PB1: 1,]);
+high_drive!(PORTC, [gpioc::PC3: 3, gpioc::PC4: 4,]);
+high_drive!(PORTD, [gpiod::PD6: 6, gpiod::PD7: 7,]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Out = Output<PushPull>;
+
+    #[test]
+    fn test_port_group_bits() {
+        type Bus = (
+            gpiod::PD0<Out>,
+            gpiod::PD1<Out>,
+            gpiod::PD2<Out>,
+            gpiod::PD3<Out>,
+        );
+        assert_eq!(<Bus as GroupPins>::MASK, 0xf);
+        assert_eq!(<Bus as GroupPins>::spread(0b1010), 0b1010);
+        assert_eq!(<Bus as GroupPins>::gather(0xffff_fff5), 0b0101);
+
+        type Scattered = (
+            gpioc::PC7<Input<PullUp>>,
+            gpioc::PC0<Input<Floating>>,
+            gpioc::PC31<Input<PullUp>>,
+        );
+        assert_eq!(<Scattered as GroupPins>::MASK, 0x8000_0081);
+        assert_eq!(<Scattered as GroupPins>::spread(0b001), 1 << 7);
+        assert_eq!(<Scattered as GroupPins>::spread(0b110), 0x8000_0001);
+        assert_eq!(<Scattered as GroupPins>::gather(1 << 7), 0b001);
+        assert_eq!(<Scattered as GroupPins>::gather(0x8000_0001), 0b110);
+        assert_eq!(<Scattered as GroupPins>::gather(0x7fff_ff7e), 0);
+    }
+}
@@ -0,0 +1,424 @@
+//! Pin multiplexing
+//!
+//! The pin functions of all on-chip peripherals are listed in a single table, which implements
+//! the pin traits below for the pins in the matching alternate function mode. Drivers take their
+//! pins by trait, so a pin configured with the wrong `into_alternate` mode is rejected at compile
+//! time. The table covers all pins of the 80-pin package. PTA14-PTA17, PTB8-PTB11, PTC12,
+//! PTC13, PTC16, PTC17 and PTE2-PTE5 are not bonded out on the 64-pin LQFP package, so their
+//! functions are unavailable there.
+
+use mkl25z4::{
+    ADC0, CMP0, DAC0, I2C0, I2C1, LPTMR0, SPI0, SPI1, TPM0, TPM1, TPM2, TSI0, UART0, UART1, UART2,
+};
+
+#[cfg(test)]
+use crate::gpio::Alternate;
+use crate::gpio::{gpioa, gpiob, gpioc, gpiod, gpioe};
+use crate::gpio::{Alternate2, Alternate3, Alternate4, Alternate5, Alternate6, Analog};
+use crate::spi::{MISO, MOSI, SCK, SS};
+
+/// UART transmit data pin
+pub trait UartTx<UART> {}
+/// UART receive data pin
+pub trait UartRx<UART> {}
+
+/// I2C clock pin
+pub trait I2cScl<I2C> {}
+/// I2C data pin
+pub trait I2cSda<I2C> {}
+
+/// TPM channel `CH` pin
+pub trait TpmChannel<TPM, const CH: u8> {}
+/// TPM external clock input `TPM_CLKIN<N>`, shared by all TPM modules
+pub trait TpmClkIn<const N: u8> {}
+
+/// ADC single-ended input channel `CH`, or the a-side of channels 4 to 7
+pub trait AdcChannel<ADC, const CH: u8> {}
+/// ADC single-ended input channel `CH` selected through the b-side input multiplexer
+pub trait AdcChannelB<ADC, const CH: u8> {}
+/// ADC differential pair `CH`, positive input
+pub trait AdcDiffPlus<ADC, const CH: u8> {}
+/// ADC differential pair `CH`, negative input
+pub trait AdcDiffMinus<ADC, const CH: u8> {}
+
+/// DAC output pin
+pub trait DacOutput<DAC> {}
+
+/// Comparator input `CH`
+pub trait CmpInput<CMP, const CH: u8> {}
+/// Comparator output pin
+pub trait CmpOutput<CMP> {}
+
+/// Touch sensing input channel `CH`
+pub trait TsiChannel<TSI, const CH: u8> {}
+
+/// CLKOUT pin
+pub trait ClkOut {}
+/// RTC_CLKOUT pin
+pub trait RtcClkOut {}
+/// LPTMR alternate pulse counter input `N`
+pub trait LptmrInput<LPTMR, const N: u8> {}
+/// External trigger input (EXTRG_IN) for the ADC and the TPM modules
+pub trait ExtrgIn {}
+/// USB clock input (USB_CLKIN)
+pub trait UsbClkIn {}
+
+macro_rules! mux_table {
+    ($($gpiox:ident::$PXi:ident: [$($ALT:ident => $Function:path: $signal:literal,)+],)+) => {
+        $($(
+            impl $Function for $gpiox::$PXi<$ALT> {}
+        )+)+
+
+        /// Pin, alternate function and signal name of every table entry
+        #[cfg(test)]
+        const MUX_TABLE: &[(&str, u8, &str)] = &[
+            $($(
+                (stringify!($PXi), <$ALT as Alternate>::MUX, $signal),
+            )+)+
+        ];
+    };
+}
+
+mux_table! {
+    gpioa::PA0: [
+        Analog => TsiChannel<TSI0, 1>: "TSI0_CH1",
+        Alternate3 => TpmChannel<TPM0, 5>: "TPM0_CH5",
+    ],
+    gpioa::PA1: [
+        Analog => TsiChannel<TSI0, 2>: "TSI0_CH2",
+        Alternate2 => UartRx<UART0>: "UART0_RX",
+        Alternate3 => TpmChannel<TPM2, 0>: "TPM2_CH0",
+    ],
+    gpioa::PA2: [
+        Analog => TsiChannel<TSI0, 3>: "TSI0_CH3",
+        Alternate2 => UartTx<UART0>: "UART0_TX",
+        Alternate3 => TpmChannel<TPM2, 1>: "TPM2_CH1",
+    ],
+    gpioa::PA3: [
+        Analog => TsiChannel<TSI0, 4>: "TSI0_CH4",
+        Alternate2 => I2cScl<I2C1>: "I2C1_SCL",
+        Alternate3 => TpmChannel<TPM0, 0>: "TPM0_CH0",
+    ],
+    gpioa::PA4: [
+        Analog => TsiChannel<TSI0, 5>: "TSI0_CH5",
+        Alternate2 => I2cSda<I2C1>: "I2C1_SDA",
+        Alternate3 => TpmChannel<TPM0, 1>: "TPM0_CH1",
+    ],
+    gpioa::PA5: [
+        Alternate2 => UsbClkIn: "USB_CLKIN",
+        Alternate3 => TpmChannel<TPM0, 2>: "TPM0_CH2",
+    ],
+    gpioa::PA12: [
+        Alternate3 => TpmChannel<TPM1, 0>: "TPM1_CH0",
+    ],
+    gpioa::PA13: [
+        Alternate3 => TpmChannel<TPM1, 1>: "TPM1_CH1",
+    ],
+    gpioa::PA14: [
+        Alternate2 => SS<SPI0>: "SPI0_PCS0",
+        Alternate3 => UartTx<UART0>: "UART0_TX",
+    ],
+    gpioa::PA15: [
+        Alternate2 => SCK<SPI0>: "SPI0_SCK",
+        Alternate3 => UartRx<UART0>: "UART0_RX",
+    ],
+    gpioa::PA16: [
+        Alternate2 => MOSI<SPI0>: "SPI0_MOSI",
+        Alternate5 => MISO<SPI0>: "SPI0_MISO",
+    ],
+    gpioa::PA17: [
+        Alternate2 => MISO<SPI0>: "SPI0_MISO",
+        Alternate5 => MOSI<SPI0>: "SPI0_MOSI",
+    ],
+    gpioa::PA18: [
+        Alternate3 => UartRx<UART1>: "UART1_RX",
+        Alternate4 => TpmClkIn<0>: "TPM_CLKIN0",
+    ],
+    gpioa::PA19: [
+        Alternate3 => UartTx<UART1>: "UART1_TX",
+        Alternate4 => TpmClkIn<1>: "TPM_CLKIN1",
+        Alternate6 => LptmrInput<LPTMR0, 1>: "LPTMR0_ALT1",
+    ],
+    gpiob::PB0: [
+        Analog => AdcChannel<ADC0, 8>: "ADC0_SE8",
+        Analog => TsiChannel<TSI0, 0>: "TSI0_CH0",
+        Alternate2 => I2cScl<I2C0>: "I2C0_SCL",
+        Alternate3 => TpmChannel<TPM1, 0>: "TPM1_CH0",
+    ],
+    gpiob::PB1: [
+        Analog => AdcChannel<ADC0, 9>: "ADC0_SE9",
+        Analog => TsiChannel<TSI0, 6>: "TSI0_CH6",
+        Alternate2 => I2cSda<I2C0>: "I2C0_SDA",
+        Alternate3 => TpmChannel<TPM1, 1>: "TPM1_CH1",
+    ],
+    gpiob::PB2: [
+        Analog => AdcChannel<ADC0, 12>: "ADC0_SE12",
+        Analog => TsiChannel<TSI0, 7>: "TSI0_CH7",
+        Alternate2 => I2cScl<I2C0>: "I2C0_SCL",
+        Alternate3 => TpmChannel<TPM2, 0>: "TPM2_CH0",
+    ],
+    gpiob::PB3: [
+        Analog => AdcChannel<ADC0, 13>: "ADC0_SE13",
+        Analog => TsiChannel<TSI0, 8>: "TSI0_CH8",
+        Alternate2 => I2cSda<I2C0>: "I2C0_SDA",
+        Alternate3 => TpmChannel<TPM2, 1>: "TPM2_CH1",
+    ],
+    gpiob::PB8: [
+        Alternate3 => ExtrgIn: "EXTRG_IN",
+    ],
+    gpiob::PB10: [
+        Alternate2 => SS<SPI1>: "SPI1_PCS0",
+    ],
+    gpiob::PB11: [
+        Alternate2 => SCK<SPI1>: "SPI1_SCK",
+    ],
+    gpiob::PB16: [
+        Analog => TsiChannel<TSI0, 9>: "TSI0_CH9",
+        Alternate2 => MOSI<SPI1>: "SPI1_MOSI",
+        Alternate3 => UartRx<UART0>: "UART0_RX",
+        Alternate4 => TpmClkIn<0>: "TPM_CLKIN0",
+        Alternate5 => MISO<SPI1>: "SPI1_MISO",
+    ],
+    gpiob::PB17: [
+        Analog => TsiChannel<TSI0, 10>: "TSI0_CH10",
+        Alternate2 => MISO<SPI1>: "SPI1_MISO",
+        Alternate3 => UartTx<UART0>: "UART0_TX",
+        Alternate4 => TpmClkIn<1>: "TPM_CLKIN1",
+        Alternate5 => MOSI<SPI1>: "SPI1_MOSI",
+    ],
+    gpiob::PB18: [
+        Analog => TsiChannel<TSI0, 11>: "TSI0_CH11",
+        Alternate3 => TpmChannel<TPM2, 0>: "TPM2_CH0",
+    ],
+    gpiob::PB19: [
+        Analog => TsiChannel<TSI0, 12>: "TSI0_CH12",
+        Alternate3 => TpmChannel<TPM2, 1>: "TPM2_CH1",
+    ],
+    gpioc::PC0: [
+        Analog => AdcChannel<ADC0, 14>: "ADC0_SE14",
+        Analog => TsiChannel<TSI0, 13>: "TSI0_CH13",
+        Alternate3 => ExtrgIn: "EXTRG_IN",
+        Alternate5 => CmpOutput<CMP0>: "CMP0_OUT",
+    ],
+    gpioc::PC1: [
+        Analog => AdcChannel<ADC0, 15>: "ADC0_SE15",
+        Analog => TsiChannel<TSI0, 14>: "TSI0_CH14",
+        Alternate2 => I2cScl<I2C1>: "I2C1_SCL",
+        Alternate4 => TpmChannel<TPM0, 0>: "TPM0_CH0",
+    ],
+    gpioc::PC2: [
+        Analog => AdcChannel<ADC0, 11>: "ADC0_SE11",
+        Analog => TsiChannel<TSI0, 15>: "TSI0_CH15",
+        Alternate2 => I2cSda<I2C1>: "I2C1_SDA",
+        Alternate4 => TpmChannel<TPM0, 1>: "TPM0_CH1",
+    ],
+    gpioc::PC3: [
+        Alternate3 => UartRx<UART1>: "UART1_RX",
+        Alternate4 => TpmChannel<TPM0, 2>: "TPM0_CH2",
+        Alternate5 => ClkOut: "CLKOUT",
+    ],
+    gpioc::PC4: [
+        Alternate2 => SS<SPI0>: "SPI0_PCS0",
+        Alternate3 => UartTx<UART1>: "UART1_TX",
+        Alternate4 => TpmChannel<TPM0, 3>: "TPM0_CH3",
+    ],
+    gpioc::PC5: [
+        Alternate2 => SCK<SPI0>: "SPI0_SCK",
+        Alternate3 => LptmrInput<LPTMR0, 2>: "LPTMR0_ALT2",
+        Alternate6 => CmpOutput<CMP0>: "CMP0_OUT",
+    ],
+    gpioc::PC6: [
+        Analog => CmpInput<CMP0, 0>: "CMP0_IN0",
+        Alternate2 => MOSI<SPI0>: "SPI0_MOSI",
+        Alternate3 => ExtrgIn: "EXTRG_IN",
+        Alternate5 => MISO<SPI0>: "SPI0_MISO",
+    ],
+    gpioc::PC7: [
+        Analog => CmpInput<CMP0, 1>: "CMP0_IN1",
+        Alternate2 => MISO<SPI0>: "SPI0_MISO",
+        Alternate5 => MOSI<SPI0>: "SPI0_MOSI",
+    ],
+    gpioc::PC8: [
+        Analog => CmpInput<CMP0, 2>: "CMP0_IN2",
+        Alternate2 => I2cScl<I2C0>: "I2C0_SCL",
+        Alternate3 => TpmChannel<TPM0, 4>: "TPM0_CH4",
+    ],
+    gpioc::PC9: [
+        Analog => CmpInput<CMP0, 3>: "CMP0_IN3",
+        Alternate2 => I2cSda<I2C0>: "I2C0_SDA",
+        Alternate3 => TpmChannel<TPM0, 5>: "TPM0_CH5",
+    ],
+    gpioc::PC10: [
+        Alternate2 => I2cScl<I2C1>: "I2C1_SCL",
+    ],
+    gpioc::PC11: [
+        Alternate2 => I2cSda<I2C1>: "I2C1_SDA",
+    ],
+    gpioc::PC12: [
+        Alternate4 => TpmClkIn<0>: "TPM_CLKIN0",
+    ],
+    gpioc::PC13: [
+        Alternate4 => TpmClkIn<1>: "TPM_CLKIN1",
+    ],
+    gpiod::PD0: [
+        Alternate2 => SS<SPI0>: "SPI0_PCS0",
+        Alternate4 => TpmChannel<TPM0, 0>: "TPM0_CH0",
+    ],
+    gpiod::PD1: [
+        Analog => AdcChannelB<ADC0, 5>: "ADC0_SE5b",
+        Alternate2 => SCK<SPI0>: "SPI0_SCK",
+        Alternate4 => TpmChannel<TPM0, 1>: "TPM0_CH1",
+    ],
+    gpiod::PD2: [
+        Alternate2 => MOSI<SPI0>: "SPI0_MOSI",
+        Alternate3 => UartRx<UART2>: "UART2_RX",
+        Alternate4 => TpmChannel<TPM0, 2>: "TPM0_CH2",
+        Alternate5 => MISO<SPI0>: "SPI0_MISO",
+    ],
+    gpiod::PD3: [
+        Alternate2 => MISO<SPI0>: "SPI0_MISO",
+        Alternate3 => UartTx<UART2>: "UART2_TX",
+        Alternate4 => TpmChannel<TPM0, 3>: "TPM0_CH3",
+        Alternate5 => MOSI<SPI0>: "SPI0_MOSI",
+    ],
+    gpiod::PD4: [
+        Alternate2 => SS<SPI1>: "SPI1_PCS0",
+        Alternate3 => UartRx<UART2>: "UART2_RX",
+        Alternate4 => TpmChannel<TPM0, 4>: "TPM0_CH4",
+    ],
+    gpiod::PD5: [
+        Analog => AdcChannelB<ADC0, 6>: "ADC0_SE6b",
+        Alternate2 => SCK<SPI1>: "SPI1_SCK",
+        Alternate3 => UartTx<UART2>: "UART2_TX",
+        Alternate4 => TpmChannel<TPM0, 5>: "TPM0_CH5",
+    ],
+    gpiod::PD6: [
+        Analog => AdcChannelB<ADC0, 7>: "ADC0_SE7b",
+        Alternate2 => MOSI<SPI1>: "SPI1_MOSI",
+        Alternate3 => UartRx<UART0>: "UART0_RX",
+        Alternate5 => MISO<SPI1>: "SPI1_MISO",
+    ],
+    gpiod::PD7: [
+        Alternate2 => MISO<SPI1>: "SPI1_MISO",
+        Alternate3 => UartTx<UART0>: "UART0_TX",
+        Alternate5 => MOSI<SPI1>: "SPI1_MOSI",
+    ],
+    gpioe::PE0: [
+        Alternate3 => UartTx<UART1>: "UART1_TX",
+        Alternate4 => RtcClkOut: "RTC_CLKOUT",
+        Alternate5 => CmpOutput<CMP0>: "CMP0_OUT",
+        Alternate6 => I2cSda<I2C1>: "I2C1_SDA",
+    ],
+    gpioe::PE1: [
+        Alternate2 => MOSI<SPI1>: "SPI1_MOSI",
+        Alternate3 => UartRx<UART1>: "UART1_RX",
+        Alternate5 => MISO<SPI1>: "SPI1_MISO",
+        Alternate6 => I2cScl<I2C1>: "I2C1_SCL",
+    ],
+    gpioe::PE2: [
+        Alternate2 => SCK<SPI1>: "SPI1_SCK",
+    ],
+    gpioe::PE3: [
+        Alternate2 => MISO<SPI1>: "SPI1_MISO",
+        Alternate5 => MOSI<SPI1>: "SPI1_MOSI",
+    ],
+    gpioe::PE4: [
+        Alternate2 => SS<SPI1>: "SPI1_PCS0",
+    ],
+    gpioe::PE20: [
+        Analog => AdcChannel<ADC0, 0>: "ADC0_SE0",
+        Analog => AdcDiffPlus<ADC0, 0>: "ADC0_DP0",
+        Alternate3 => TpmChannel<TPM1, 0>: "TPM1_CH0",
+        Alternate4 => UartTx<UART0>: "UART0_TX",
+    ],
+    gpioe::PE21: [
+        Analog => AdcChannel<ADC0, 4>: "ADC0_SE4a",
+        Analog => AdcDiffMinus<ADC0, 0>: "ADC0_DM0",
+        Alternate3 => TpmChannel<TPM1, 1>: "TPM1_CH1",
+        Alternate4 => UartRx<UART0>: "UART0_RX",
+    ],
+    gpioe::PE22: [
+        Analog => AdcChannel<ADC0, 3>: "ADC0_SE3",
+        Analog => AdcDiffPlus<ADC0, 3>: "ADC0_DP3",
+        Alternate3 => TpmChannel<TPM2, 0>: "TPM2_CH0",
+        Alternate4 => UartTx<UART2>: "UART2_TX",
+    ],
+    gpioe::PE23: [
+        Analog => AdcChannel<ADC0, 7>: "ADC0_SE7a",
+        Analog => AdcDiffMinus<ADC0, 3>: "ADC0_DM3",
+        Alternate3 => TpmChannel<TPM2, 1>: "TPM2_CH1",
+        Alternate4 => UartRx<UART2>: "UART2_RX",
+    ],
+    gpioe::PE24: [
+        Alternate3 => TpmChannel<TPM0, 0>: "TPM0_CH0",
+        Alternate5 => I2cScl<I2C0>: "I2C0_SCL",
+    ],
+    gpioe::PE25: [
+        Alternate3 => TpmChannel<TPM0, 1>: "TPM0_CH1",
+        Alternate5 => I2cSda<I2C0>: "I2C0_SDA",
+    ],
+    gpioe::PE29: [
+        Analog => CmpInput<CMP0, 5>: "CMP0_IN5",
+        Analog => AdcChannelB<ADC0, 4>: "ADC0_SE4b",
+        Alternate3 => TpmChannel<TPM0, 2>: "TPM0_CH2",
+        Alternate4 => TpmClkIn<0>: "TPM_CLKIN0",
+    ],
+    gpioe::PE30: [
+        Analog => DacOutput<DAC0>: "DAC0_OUT",
+        Analog => AdcChannel<ADC0, 23>: "ADC0_SE23",
+        Analog => CmpInput<CMP0, 4>: "CMP0_IN4",
+        Alternate3 => TpmChannel<TPM0, 3>: "TPM0_CH3",
+        Alternate4 => TpmClkIn<1>: "TPM_CLKIN1",
+    ],
+    gpioe::PE31: [
+        Alternate3 => TpmChannel<TPM0, 4>: "TPM0_CH4",
+    ],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mux_table() {
+        for (n, &(pin, mux, signal)) in MUX_TABLE.iter().enumerate() {
+            // Alternate function 1 is always GPIO.
+            assert!(
+                mux != 1 && mux <= 7,
+                "{} {}: invalid mux {}",
+                pin,
+                signal,
+                mux
+            );
+
+            for &(other_pin, other_mux, other_signal) in &MUX_TABLE[n + 1..] {
+                if pin != other_pin {
+                    // Analog signals are connected to a single pin.
+                    assert!(
+                        mux != 0 || other_mux != 0 || signal != other_signal,
+                        "{} is connected to {} and {}",
+                        signal,
+                        pin,
+                        other_pin
+                    );
+                    continue;
+                }
+                assert!(
+                    signal != other_signal,
+                    "{} is listed twice for {}",
+                    signal,
+                    pin
+                );
+                // Several analog functions can share a pin, digital functions cannot.
+                assert!(
+                    mux == 0 || mux != other_mux,
+                    "{} and {} both use mux {} of {}",
+                    signal,
+                    other_signal,
+                    mux,
+                    pin
+                );
+            }
+        }
+    }
+}
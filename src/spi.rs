@@ -4,12 +4,6 @@ use mkl25z4::{SIM, SPI0, SPI1};
 use nb;
 
 use crate::clocks::Clocks;
-use crate::gpio::gpioa::{PA14, PA15, PA16, PA17};
-use crate::gpio::gpiob::{PB10, PB11, PB16, PB17};
-use crate::gpio::gpioc::{PC4, PC5, PC6, PC7};
-use crate::gpio::gpiod::{PD0, PD1, PD2, PD3, PD4, PD5, PD6, PD7};
-use crate::gpio::gpioe::{PE1, PE2, PE3, PE4};
-use crate::gpio::{Alternate2, Alternate5};
 use crate::time::Hertz;
 
 /// SPI error
@@ -21,58 +15,18 @@ pub enum Error {
     _Extensible,
 }
 
+/// SPI MOSI pin, implemented by the pins listed in the `mux` table
 pub trait MOSI<SPI> {}
 
-impl MOSI<SPI0> for PA16<Alternate2> {}
-impl MOSI<SPI0> for PA17<Alternate5> {}
-impl MOSI<SPI0> for PC6<Alternate2> {}
-impl MOSI<SPI0> for PC7<Alternate5> {}
-impl MOSI<SPI0> for PD2<Alternate2> {}
-impl MOSI<SPI0> for PD3<Alternate5> {}
-
-impl MOSI<SPI1> for PB16<Alternate2> {}
-impl MOSI<SPI1> for PB17<Alternate5> {}
-impl MOSI<SPI1> for PD6<Alternate2> {}
-impl MOSI<SPI1> for PD7<Alternate5> {}
-impl MOSI<SPI1> for PE1<Alternate2> {}
-impl MOSI<SPI1> for PE3<Alternate5> {}
-
+/// SPI MISO pin
 pub trait MISO<SPI> {}
 
-impl MISO<SPI0> for PA16<Alternate5> {}
-impl MISO<SPI0> for PA17<Alternate2> {}
-impl MISO<SPI0> for PC6<Alternate5> {}
-impl MISO<SPI0> for PC7<Alternate2> {}
-impl MISO<SPI0> for PD2<Alternate5> {}
-impl MISO<SPI0> for PD3<Alternate2> {}
-
-impl MISO<SPI1> for PB16<Alternate5> {}
-impl MISO<SPI1> for PB17<Alternate2> {}
-impl MISO<SPI1> for PD6<Alternate5> {}
-impl MISO<SPI1> for PD7<Alternate2> {}
-impl MISO<SPI1> for PE1<Alternate5> {}
-impl MISO<SPI1> for PE3<Alternate2> {}
-
+/// SPI clock pin
 pub trait SCK<SPI> {}
 
-impl SCK<SPI0> for PA15<Alternate2> {}
-impl SCK<SPI0> for PD1<Alternate2> {}
-impl SCK<SPI0> for PC5<Alternate2> {}
-
-impl SCK<SPI1> for PB11<Alternate2> {}
-impl SCK<SPI1> for PD5<Alternate2> {}
-impl SCK<SPI1> for PE2<Alternate2> {}
-
+/// SPI slave select pin
 pub trait SS<SPI> {}
 
-impl SS<SPI0> for PA14<Alternate2> {}
-impl SS<SPI0> for PC4<Alternate2> {}
-impl SS<SPI0> for PD0<Alternate2> {}
-
-impl SS<SPI1> for PB10<Alternate2> {}
-impl SS<SPI1> for PD4<Alternate2> {}
-impl SS<SPI1> for PE4<Alternate2> {}
-
 pub struct Spi<SPI, MOSIPin, MISOPin, SCKPin> {
     spi: SPI,
     mosi: MOSIPin,
@@ -2,10 +2,12 @@
 
 //! Time units
 
-use mkl25z4::{PIT, SIM};
+use core::marker::PhantomData;
+
+use mkl25z4::PIT;
 
-use crate::clocks::Clocks;
 use crate::hal::blocking::delay::{DelayMs, DelayUs};
+use crate::timer::{PitChannel, PitChannel0, PitChannel1};
 
 /// Bits per second
 #[derive(Clone, Copy)]
@@ -35,6 +37,10 @@ pub struct MilliSeconds(pub u32);
 #[derive(Clone, Copy)]
 pub struct MicroSeconds(pub u32);
 
+/// Microseconds with a 64-bit range, which holds any of the other durations without loss
+#[derive(Clone, Copy)]
+pub struct MicroSeconds64(pub u64);
+
 /// Extension trait that adds convenience methods to the `u32` type
 pub trait U32Ext {
     /// Wrap in `Bps`
@@ -125,6 +131,24 @@ impl Into<MicroSeconds> for MilliSeconds {
     }
 }
 
+impl Into<MicroSeconds64> for Seconds {
+    fn into(self) -> MicroSeconds64 {
+        MicroSeconds64(self.0 as u64 * 1_000_000)
+    }
+}
+
+impl Into<MicroSeconds64> for MilliSeconds {
+    fn into(self) -> MicroSeconds64 {
+        MicroSeconds64(self.0 as u64 * 1_000)
+    }
+}
+
+impl Into<MicroSeconds64> for MicroSeconds {
+    fn into(self) -> MicroSeconds64 {
+        MicroSeconds64(self.0 as u64)
+    }
+}
+
 pub trait MonoTimer {
     /// Returns the frequency at which the monotonic timer is operating.
     fn frequency(&self) -> Hertz;
@@ -140,7 +164,7 @@ pub trait MonoTimer {
 }
 
 // TODO: Deduplicate the code here and for CopyableMonoTimer.
-impl DelayMs<u8> for NonCopyableMonoTimer {
+impl<CH: PitChannel> DelayMs<u8> for NonCopyableMonoTimer<CH> {
     fn delay_ms(&mut self, ms: u8) {
         let freq = self.frequency();
         // TODO: Check for overflow and employ different strategy?
@@ -151,7 +175,7 @@ impl DelayMs<u8> for NonCopyableMonoTimer {
     }
 }
 
-impl DelayUs<u8> for NonCopyableMonoTimer {
+impl<CH: PitChannel> DelayUs<u8> for NonCopyableMonoTimer<CH> {
     fn delay_us(&mut self, us: u8) {
         // TODO: Most of the code is not needed.
         let freq = self.frequency();
@@ -165,7 +189,7 @@ impl DelayUs<u8> for NonCopyableMonoTimer {
     }
 }
 
-impl DelayMs<u32> for NonCopyableMonoTimer {
+impl<CH: PitChannel> DelayMs<u32> for NonCopyableMonoTimer<CH> {
     fn delay_ms(&mut self, ms: u32) {
         let freq = self.frequency();
         // TODO: Check for overflow and employ different strategy?
@@ -176,7 +200,7 @@ impl DelayMs<u32> for NonCopyableMonoTimer {
     }
 }
 
-impl DelayUs<u32> for NonCopyableMonoTimer {
+impl<CH: PitChannel> DelayUs<u32> for NonCopyableMonoTimer<CH> {
     fn delay_us(&mut self, us: u32) {
         let freq = self.frequency();
         self.delay_ms(us / 1000);
@@ -190,36 +214,29 @@ impl DelayUs<u32> for NonCopyableMonoTimer {
 }
 
 /// A monotonic nondecreasing timer
-pub struct NonCopyableMonoTimer {
-    pit: PIT,
+pub struct NonCopyableMonoTimer<CH> {
+    channel: CH,
     frequency: Hertz,
 }
 
-impl NonCopyableMonoTimer {
-    /// Creates a new `Monotonic` timer
-    pub fn new(pit: PIT, clocks: Clocks, sim: &mut SIM) -> Self {
-        sim.scgc6.modify(|_, w| w.pit().set_bit());
-        unsafe {
-            pit.mcr.write(|w| w.bits(0));
-            pit.tctrl0.modify(|_, w| w.tie().clear_bit());
-            pit.ldval0.write(|w| w.bits(0xffffffff));
-            pit.tctrl0.write(|w| w.bits(0x0).ten().set_bit());
-        }
+impl<CH: PitChannel> NonCopyableMonoTimer<CH> {
+    /// Creates a new `Monotonic` timer from a PIT channel
+    pub fn new(mut channel: CH) -> Self {
+        channel.run_free();
 
         NonCopyableMonoTimer {
-            pit: pit,
-            frequency: clocks.busclk(),
+            frequency: channel.frequency(),
+            channel,
         }
     }
 
-    pub fn free(self, sim: &mut SIM) -> PIT {
-        self.pit.tctrl0.modify(|_, w| w.ten().clear_bit());
-        sim.scgc6.modify(|_, w| w.pit().clear_bit());
-        self.pit
+    pub fn free(mut self) -> CH {
+        self.channel.stop();
+        self.channel
     }
 }
 
-impl MonoTimer for NonCopyableMonoTimer {
+impl<CH: PitChannel> MonoTimer for NonCopyableMonoTimer<CH> {
     /// Returns the frequency at which the monotonic timer is operating at
     fn frequency(&self) -> Hertz {
         self.frequency
@@ -229,27 +246,36 @@ impl MonoTimer for NonCopyableMonoTimer {
     fn now(&self) -> Instant {
         Instant {
             // The PIT counts from 0xffffffff down to 0.
-            now: 0xffffffff_u32.wrapping_sub(self.pit.cval0.read().bits()),
+            now: 0xffffffff_u32.wrapping_sub(CH::counter()),
         }
     }
 }
 
 /// A monotonic nondecreasing timer - like MonoTimer, but the resources cannot
 /// be released, so the type can implement Copy.
-#[derive(Clone, Copy)]
-pub struct CopyableMonoTimer {
+pub struct CopyableMonoTimer<CH> {
     frequency: Hertz,
+    _channel: PhantomData<CH>,
 }
 
-impl CopyableMonoTimer {
-    pub fn new(timer: NonCopyableMonoTimer) -> Self {
+impl<CH> Clone for CopyableMonoTimer<CH> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<CH> Copy for CopyableMonoTimer<CH> {}
+
+impl<CH: PitChannel> CopyableMonoTimer<CH> {
+    pub fn new(timer: NonCopyableMonoTimer<CH>) -> Self {
         CopyableMonoTimer {
             frequency: timer.frequency,
+            _channel: PhantomData,
         }
     }
 }
 
-impl MonoTimer for CopyableMonoTimer {
+impl<CH: PitChannel> MonoTimer for CopyableMonoTimer<CH> {
     /// Returns the frequency at which the monotonic timer is operating at
     fn frequency(&self) -> Hertz {
         self.frequency
@@ -259,12 +285,12 @@ impl MonoTimer for CopyableMonoTimer {
     fn now(&self) -> Instant {
         Instant {
             // The PIT counts from 0xffffffff down to 0.
-            now: 0xffffffff_u32.wrapping_sub(unsafe { (*PIT::ptr()).cval0.read().bits() }),
+            now: 0xffffffff_u32.wrapping_sub(CH::counter()),
         }
     }
 }
 
-impl DelayMs<u8> for CopyableMonoTimer {
+impl<CH: PitChannel> DelayMs<u8> for CopyableMonoTimer<CH> {
     fn delay_ms(&mut self, ms: u8) {
         let freq = self.frequency();
         // TODO: Check for overflow and employ different strategy?
@@ -275,7 +301,7 @@ impl DelayMs<u8> for CopyableMonoTimer {
     }
 }
 
-impl DelayUs<u8> for CopyableMonoTimer {
+impl<CH: PitChannel> DelayUs<u8> for CopyableMonoTimer<CH> {
     fn delay_us(&mut self, us: u8) {
         // TODO: Most of the code is not needed.
         let freq = self.frequency();
@@ -289,7 +315,7 @@ impl DelayUs<u8> for CopyableMonoTimer {
     }
 }
 
-impl DelayMs<u32> for CopyableMonoTimer {
+impl<CH: PitChannel> DelayMs<u32> for CopyableMonoTimer<CH> {
     fn delay_ms(&mut self, ms: u32) {
         let freq = self.frequency();
         // TODO: Check for overflow and employ different strategy?
@@ -300,7 +326,7 @@ impl DelayMs<u32> for CopyableMonoTimer {
     }
 }
 
-impl DelayUs<u32> for CopyableMonoTimer {
+impl<CH: PitChannel> DelayUs<u32> for CopyableMonoTimer<CH> {
     fn delay_us(&mut self, us: u32) {
         let freq = self.frequency();
         self.delay_ms(us / 1000);
@@ -325,3 +351,123 @@ impl Instant {
         other.now.wrapping_sub(self.now)
     }
 }
+
+/// A 64-bit monotonic timer built from both PIT channels
+///
+/// Channel 1 is chained to channel 0 and the two counters are read as the 64-bit PIT lifetime
+/// timer, so the timer does not overflow for thousands of years.
+pub struct LifetimeTimer {
+    channel0: PitChannel0,
+    channel1: PitChannel1,
+    frequency: Hertz,
+}
+
+impl LifetimeTimer {
+    /// Creates a new lifetime timer from the two PIT channels
+    pub fn new(mut channel0: PitChannel0, mut channel1: PitChannel1) -> Self {
+        channel0.stop();
+        channel1.stop();
+        // NOTE(unsafe) both channels are owned by the timer
+        let pit = unsafe { &*PIT::ptr() };
+        unsafe {
+            pit.ldval1.write(|w| w.bits(0xffffffff));
+            pit.ldval0.write(|w| w.bits(0xffffffff));
+        }
+        // Channel 1 has to be started before channel 0.
+        pit.tctrl1.write(|w| w.chn().set_bit().ten().set_bit());
+        pit.tctrl0.write(|w| w.ten().set_bit());
+
+        LifetimeTimer {
+            frequency: channel0.frequency(),
+            channel0,
+            channel1,
+        }
+    }
+
+    /// Returns a 64-bit `Instant64` corresponding to "now"
+    pub fn now64(&self) -> Instant64 {
+        // NOTE(unsafe) atomic reads, reading LTMR64H latches the value of LTMR64L
+        let pit = unsafe { &*PIT::ptr() };
+        let high = pit.ltmr64h.read().bits();
+        let low = pit.ltmr64l.read().bits();
+        Instant64 {
+            now: lifetime_ticks(high, low),
+        }
+    }
+
+    pub fn free(mut self) -> (PitChannel0, PitChannel1) {
+        self.channel0.stop();
+        self.channel1.stop();
+        // NOTE(unsafe) both channels are owned by the timer
+        unsafe { &*PIT::ptr() }
+            .tctrl1
+            .modify(|_, w| w.chn().clear_bit());
+        (self.channel0, self.channel1)
+    }
+}
+
+impl MonoTimer for LifetimeTimer {
+    /// Returns the frequency at which the monotonic timer is operating at
+    fn frequency(&self) -> Hertz {
+        self.frequency
+    }
+
+    /// Returns an `Instant` corresponding to "now"
+    fn now(&self) -> Instant {
+        Instant {
+            now: self.now64().now as u32,
+        }
+    }
+}
+
+/// Converts the lifetime timer registers, which count down from 0xffffffff_ffffffff, into the
+/// number of elapsed ticks
+fn lifetime_ticks(high: u32, low: u32) -> u64 {
+    !(((high as u64) << 32) | low as u64)
+}
+
+/// A 64-bit measurement of a monotonically nondecreasing clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instant64 {
+    now: u64,
+}
+
+impl Instant64 {
+    /// Ticks elapsed since the `Instant64` was created
+    pub fn elapsed(&self, other: Instant64) -> u64 {
+        other.now.wrapping_sub(self.now)
+    }
+
+    /// Ticks elapsed from `earlier` to this `Instant64`, or zero if `earlier` is later
+    pub fn duration_since(&self, earlier: Instant64) -> u64 {
+        self.now.saturating_sub(earlier.now)
+    }
+
+    /// Ticks elapsed since the timer was started
+    pub fn ticks(&self) -> u64 {
+        self.now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifetime_instant() {
+        assert_eq!(lifetime_ticks(0xffffffff, 0xffffffff), 0);
+        assert_eq!(lifetime_ticks(0xffffffff, 0), 0xffffffff);
+        assert_eq!(lifetime_ticks(0xfffffffe, 0xffffffff), 0x1_0000_0000);
+
+        let earlier = Instant64 {
+            now: lifetime_ticks(0xffffffff, 0x00000010),
+        };
+        let later = Instant64 {
+            now: lifetime_ticks(0xfffffffe, 0xfffffff0),
+        };
+        assert_eq!(earlier.elapsed(later), 0x20);
+        assert_eq!(later.duration_since(earlier), 0x20);
+        assert_eq!(earlier.duration_since(later), 0);
+        assert!(earlier < later);
+    }
+}
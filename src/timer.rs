@@ -1,33 +1,121 @@
-use crate::clocks::Clocks;
+use crate::clocks::{ClockError, Clocks};
 use crate::hal::timer::{CountDown, Periodic};
-use crate::time::{Hertz, MilliSeconds};
+use crate::mux::LptmrInput;
+use crate::time::{Hertz, MicroSeconds64};
 use crate::void::Void;
 
-use mkl25z4::{LPTMR0, PIT, SIM};
+use mkl25z4::{lptmr0, pit, LPTMR0, PIT, SIM};
 
+/// Interrupt events
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Event {
+    /// The timer has reached its timeout, or the pulse counter its compare value
     Update,
 }
 
 pub trait TimerInterrupt {
     fn enable_interrupt(&self);
     fn disable_interrupt(&self);
+
+    /// Enables the interrupt for `event`
+    fn listen(&mut self, event: Event);
+
+    /// Disables the interrupt for `event`
+    fn unlisten(&mut self, event: Event);
+
+    /// Clears the interrupt flag of `event`
+    fn clear_interrupt(&mut self, event: Event);
+
+    /// Returns whether the interrupt flag of `event` is set
+    fn is_pending(&self, event: Event) -> bool;
+}
+
+/// Input clock of the LPTMR
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LptmrClock {
+    /// MCG internal reference clock
+    Mcgirclk,
+    /// Low power oscillator (1 kHz)
+    Lpo,
+    /// 32 kHz external reference clock
+    Erclk32k,
+    /// System oscillator output
+    Oscerclk,
+}
+
+impl LptmrClock {
+    /// Returns the frequency of the clock, or `None` if it is not enabled
+    fn frequency(self, clocks: &Clocks) -> Option<Hertz> {
+        match self {
+            LptmrClock::Mcgirclk => clocks.mcgirclk(),
+            LptmrClock::Lpo => Some(clocks.lpo()),
+            LptmrClock::Erclk32k => clocks.erclk32k(),
+            LptmrClock::Oscerclk => clocks.oscerclk(),
+        }
+    }
+
+    /// Returns the prescaler clock select (PSR[PCS]) value of the clock
+    fn pcs(self) -> lptmr0::psr::PCS_A {
+        match self {
+            LptmrClock::Mcgirclk => lptmr0::psr::PCS_A::_00,
+            LptmrClock::Lpo => lptmr0::psr::PCS_A::_01,
+            LptmrClock::Erclk32k => lptmr0::psr::PCS_A::_10,
+            LptmrClock::Oscerclk => lptmr0::psr::PCS_A::_11,
+        }
+    }
 }
 
 pub struct Timer<TIM> {
     tim: TIM,
-    clocks: Clocks,
+    source: LptmrClock,
+    input: Hertz,
 }
 
 impl Timer<LPTMR0> {
+    /// Creates a timer which counts the 1 kHz LPO
     pub fn lptmr0<T>(lptmr: LPTMR0, timeout: T, clocks: Clocks, sim: &mut SIM) -> Self
     where
-        T: Into<MilliSeconds>,
+        T: Into<MicroSeconds64>,
+    {
+        // The LPO is always enabled.
+        Self::lptmr0_from_input(lptmr, LptmrClock::Lpo, clocks.lpo(), timeout, sim)
+    }
+
+    /// Creates a timer which counts the specified input clock
+    ///
+    /// An error is returned if the selected clock is not enabled.
+    pub fn lptmr0_with_clock<T>(
+        lptmr: LPTMR0,
+        source: LptmrClock,
+        timeout: T,
+        clocks: Clocks,
+        sim: &mut SIM,
+    ) -> Result<Self, ClockError>
+    where
+        T: Into<MicroSeconds64>,
+    {
+        let input = source.frequency(&clocks).ok_or(ClockError::ClockDisabled)?;
+        Ok(Self::lptmr0_from_input(lptmr, source, input, timeout, sim))
+    }
+
+    fn lptmr0_from_input<T>(
+        lptmr: LPTMR0,
+        source: LptmrClock,
+        input: Hertz,
+        timeout: T,
+        sim: &mut SIM,
+    ) -> Self
+    where
+        T: Into<MicroSeconds64>,
     {
         sim.scgc5.modify(|_, w| w.lptmr().set_bit());
+        unsafe {
+            lptmr.csr.write(|w| w.bits(0)); // Disable the timer and select time counter mode.
+        }
         let mut timer = Timer {
             tim: lptmr,
-            clocks: clocks,
+            source,
+            input,
         };
         timer.start(timeout);
         timer
@@ -41,45 +129,60 @@ impl Timer<LPTMR0> {
     }
 }
 
+/// Returns the prescaler setting and the compare value of the LPTMR for a timeout in
+/// microseconds
+///
+/// The prescaler divides the input clock by 2^(prescale + 1), `None` bypasses the prescaler.
+/// Timeouts which are too long for the input clock are clamped to the longest possible period.
+fn get_lptmr_dividers(input: Hertz, timeout_us: u64) -> (Option<u8>, u16) {
+    // Saturate, the result is clamped to the longest period anyway.
+    let ticks = ((input.0 as u64).saturating_mul(timeout_us) / 1_000_000).max(1);
+    // The period of the timer is the compare value plus one.
+    if ticks <= 0x1_0000 {
+        return (None, (ticks - 1) as u16);
+    }
+    for prescale in 0..16 {
+        let divider = 2u64 << prescale;
+        let compare = (ticks + divider / 2) / divider;
+        if compare <= 0x1_0000 {
+            return (Some(prescale), (compare.max(1) - 1) as u16);
+        }
+    }
+    (Some(15), 0xffff)
+}
+
 impl CountDown for Timer<LPTMR0> {
-    type Time = MilliSeconds;
+    type Time = MicroSeconds64;
 
     fn start<T>(&mut self, timeout: T)
     where
-        T: Into<MilliSeconds>,
+        T: Into<MicroSeconds64>,
     {
-        let timeout = timeout.into();
-
-        let ps_ratio = timeout.0 >> 16;
-        let prescale = (ps_ratio + 1).next_power_of_two();
-        let compare = if ps_ratio == 0 {
-            timeout.0
-        } else {
-            timeout.0 / prescale
-        };
+        let (prescale, compare) = get_lptmr_dividers(self.input, timeout.into().0);
 
-        unsafe {
-            self.tim.csr.write(|w| w.bits(0)); // Disable the timer.
-        }
+        // Disable the timer, but keep the interrupt enabled.
         self.tim
-            .cmr
-            .write(|w| unsafe { w.compare().bits(compare as u16) });
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().clear_bit());
+        self.tim.cmr.write(|w| unsafe { w.compare().bits(compare) });
+        let source = self.source;
         self.tim.psr.write(|w| {
-            let w = w.pcs()._01(); // Select the 1kHz LPO as input.
-            let w = if ps_ratio == 0 {
-                w.pbyp().set_bit() // Bypass the prescaler.
-            } else {
-                let prescale_bits = prescale.trailing_zeros() - 1;
-                w.pbyp().clear_bit().prescale().bits(prescale_bits as u8)
-            };
-            w
+            let w = w.pcs().variant(source.pcs());
+            match prescale {
+                None => w.pbyp().set_bit(), // Bypass the prescaler.
+                Some(prescale) => w.pbyp().clear_bit().prescale().bits(prescale),
+            }
         });
         //self.tim.csr.modify(|_, w| w.tfc().clear_bit()); // Periodic counter
-        self.tim.csr.modify(|_, w| w.ten().set_bit());
+        // Clear a pending timeout and enable the timer.
+        self.tim
+            .csr
+            .modify(|_, w| w.tcf().set_bit().ten().set_bit());
     }
 
     fn wait(&mut self) -> nb::Result<(), Void> {
-        if self.tim.csr.read().tcf().bit_is_set() {
+        if self.is_pending(Event::Update) {
+            self.clear_interrupt(Event::Update);
             Ok(())
         } else {
             Err(nb::Error::WouldBlock)
@@ -87,67 +190,453 @@ impl CountDown for Timer<LPTMR0> {
     }
 }
 
+/// Enables or disables the LPTMR interrupt
+fn lptmr_set_interrupt(lptmr: &LPTMR0, enable: bool) {
+    // TCF is cleared by writing 1, so it has to be written as 0 here.
+    lptmr
+        .csr
+        .modify(|_, w| w.tcf().clear_bit().tie().bit(enable));
+}
+
+/// Clears the compare flag of the LPTMR
+fn lptmr_clear_interrupt(lptmr: &LPTMR0) {
+    lptmr.csr.modify(|_, w| w.tcf().set_bit());
+}
+
+/// Returns whether the compare flag of the LPTMR is set
+fn lptmr_is_pending(lptmr: &LPTMR0) -> bool {
+    lptmr.csr.read().tcf().bit_is_set()
+}
+
 impl TimerInterrupt for Timer<LPTMR0> {
     fn enable_interrupt(&self) {
-        self.tim.csr.modify(|_, w| w.tie().set_bit());
+        lptmr_set_interrupt(&self.tim, true);
     }
     fn disable_interrupt(&self) {
-        self.tim.csr.modify(|_, w| w.tie().clear_bit());
+        lptmr_set_interrupt(&self.tim, false);
+    }
+
+    fn listen(&mut self, event: Event) {
+        match event {
+            Event::Update => self.enable_interrupt(),
+        }
+    }
+
+    fn unlisten(&mut self, event: Event) {
+        match event {
+            Event::Update => self.disable_interrupt(),
+        }
+    }
+
+    fn clear_interrupt(&mut self, event: Event) {
+        match event {
+            Event::Update => lptmr_clear_interrupt(&self.tim),
+        }
+    }
+
+    fn is_pending(&self, event: Event) -> bool {
+        match event {
+            Event::Update => lptmr_is_pending(&self.tim),
+        }
     }
-    // TODO: Function to acknowledge the interrupt.
 }
 
 impl Periodic for Timer<LPTMR0> {}
 
-impl Timer<PIT> {
-    pub fn pit<T>(pit: PIT, timeout: T, clocks: Clocks, sim: &mut SIM) -> Self
-    where
-        T: Into<Hertz>,
-    {
-        sim.scgc6.modify(|_, w| w.pit().set_bit());
-        let mut timer = Timer {
-            tim: pit,
-            clocks: clocks,
-        };
-        timer.start(timeout);
-        timer
-    }
+/// Edge of the input pin counted by the pulse counter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PulsePolarity {
+    /// Count rising edges
+    RisingEdge,
+    /// Count falling edges
+    FallingEdge,
 }
 
-impl CountDown for Timer<PIT> {
-    type Time = Hertz;
+/// LPTMR counting pulses on the LPTMR_ALT1 (PTA19) or LPTMR_ALT2 (PTC5) input
+///
+/// The counter keeps running in all low power modes. It counts freely and wraps around at
+/// 0xffff, the compare value only controls when the interrupt flag is set.
+pub struct PulseCounter<PIN> {
+    lptmr: LPTMR0,
+    pin: PIN,
+}
 
-    fn start<T>(&mut self, timeout: T)
+impl<PIN> PulseCounter<PIN> {
+    /// Creates a pulse counter on the specified input pin
+    pub fn new<const N: u8>(lptmr: LPTMR0, pin: PIN, polarity: PulsePolarity, sim: &mut SIM) -> Self
     where
-        T: Into<Hertz>,
+        PIN: LptmrInput<LPTMR0, N>,
     {
+        sim.scgc5.modify(|_, w| w.lptmr().set_bit());
         unsafe {
-            self.tim.mcr.write(|w| w.bits(0));
-            let compare = self.clocks.busclk().0 / timeout.into().0;
-            self.tim.ldval0.write(|w| w.bits(compare));
-            self.tim.tctrl0.write(|w| w.bits(0x0).ten().clear_bit());
-            self.tim.tflg0.write(|w| w.bits(1));
-            self.tim.tctrl0.write(|w| w.bits(0x0).ten().set_bit());
+            lptmr.csr.write(|w| w.bits(0)); // Disable the timer.
         }
+        lptmr.psr.write(|w| w.pbyp().set_bit()); // Count every pulse.
+        lptmr.cmr.write(|w| unsafe { w.compare().bits(0xffff) });
+        lptmr.csr.write(|w| {
+            let w = w
+                .tms()
+                .set_bit() // Pulse counter mode
+                .tfc()
+                .set_bit() // Reset the counter on overflow only
+                .tps()
+                .bits(N);
+            match polarity {
+                PulsePolarity::RisingEdge => w.tpp().clear_bit(),
+                PulsePolarity::FallingEdge => w.tpp().set_bit(),
+            }
+        });
+        lptmr.csr.modify(|_, w| w.tcf().clear_bit().ten().set_bit());
+
+        PulseCounter { lptmr, pin }
     }
 
-    fn wait(&mut self) -> nb::Result<(), Void> {
-        if self.tim.tflg0.read().tif().bit_is_set() {
-            unsafe {
-                self.tim.tflg0.write(|w| w.bits(1));
-            }
-            Ok(())
-        } else {
-            Err(nb::Error::WouldBlock)
+    /// Enables the glitch filter of the input
+    ///
+    /// A change of the input is only recognized once it has been stable for 2^`prescale` rising
+    /// edges of the selected clock. Every recognized pulse is still counted. `prescale` is
+    /// clamped to the supported range of 1 to 15. The counter is reset.
+    ///
+    /// An error is returned if the selected clock is not enabled.
+    pub fn enable_glitch_filter(
+        &mut self,
+        clock: LptmrClock,
+        prescale: u8,
+        clocks: Clocks,
+    ) -> Result<(), ClockError> {
+        if clock.frequency(&clocks).is_none() {
+            return Err(ClockError::ClockDisabled);
         }
+        let prescale = prescale.clamp(1, 15);
+
+        self.lptmr
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().clear_bit());
+        self.lptmr.psr.write(|w| {
+            w.pcs()
+                .variant(clock.pcs())
+                .pbyp()
+                .clear_bit()
+                .prescale()
+                .bits(prescale)
+        });
+        self.lptmr
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().set_bit());
+        Ok(())
+    }
+
+    /// Disables the glitch filter, so that every pulse is counted as soon as it occurs
+    ///
+    /// The counter is reset.
+    pub fn disable_glitch_filter(&mut self) {
+        self.lptmr
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().clear_bit());
+        self.lptmr.psr.modify(|_, w| w.pbyp().set_bit());
+        self.lptmr
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().set_bit());
+    }
+
+    /// Sets the interrupt flag once `pulses` pulses have been counted
+    ///
+    /// The counter is reset.
+    pub fn set_compare(&mut self, pulses: u16) {
+        self.lptmr
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().clear_bit());
+        self.lptmr
+            .cmr
+            .write(|w| unsafe { w.compare().bits(pulses.max(1) - 1) });
+        self.lptmr
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().set_bit());
+    }
+
+    /// Returns the number of pulses counted so far
+    pub fn count(&mut self) -> u16 {
+        unsafe {
+            // The counter value is latched by writing to CNR.
+            self.lptmr.cnr.write(|w| w.bits(0));
+            self.lptmr.cnr.read().bits() as u16
+        }
+    }
+
+    /// Resets the counter to zero
+    pub fn reset(&mut self) {
+        self.lptmr
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().clear_bit());
+        self.lptmr
+            .csr
+            .modify(|_, w| w.tcf().clear_bit().ten().set_bit());
+    }
+
+    /// Stops the pulse counter and releases the LPTMR and the input pin
+    pub fn free(self) -> (LPTMR0, PIN) {
+        unsafe {
+            self.lptmr.csr.write(|w| w.bits(0)); // Disable the timer.
+        }
+        (self.lptmr, self.pin)
     }
 }
 
-impl TimerInterrupt for Timer<PIT> {
+impl<PIN> TimerInterrupt for PulseCounter<PIN> {
     fn enable_interrupt(&self) {
-        self.tim.tctrl0.modify(|_, w| w.tie().set_bit());
+        lptmr_set_interrupt(&self.lptmr, true);
     }
     fn disable_interrupt(&self) {
-        self.tim.tctrl0.modify(|_, w| w.tie().clear_bit());
+        lptmr_set_interrupt(&self.lptmr, false);
+    }
+
+    fn listen(&mut self, event: Event) {
+        match event {
+            Event::Update => self.enable_interrupt(),
+        }
+    }
+
+    fn unlisten(&mut self, event: Event) {
+        match event {
+            Event::Update => self.disable_interrupt(),
+        }
+    }
+
+    fn clear_interrupt(&mut self, event: Event) {
+        match event {
+            Event::Update => lptmr_clear_interrupt(&self.lptmr),
+        }
+    }
+
+    fn is_pending(&self, event: Event) -> bool {
+        match event {
+            Event::Update => lptmr_is_pending(&self.lptmr),
+        }
+    }
+}
+
+/// Extension trait to split the PIT into its independent channels
+pub trait PitExt {
+    /// Enables the PIT and splits it into its two channels
+    fn split(self, clocks: Clocks, sim: &mut SIM) -> (PitChannel0, PitChannel1);
+
+    /// Stops both channels, disables the PIT and releases it
+    ///
+    /// The PIT has to be split again with new `Clocks` after the clock configuration changed.
+    fn join(channel0: PitChannel0, channel1: PitChannel1, sim: &mut SIM) -> Self;
+}
+
+impl PitExt for PIT {
+    fn split(self, clocks: Clocks, sim: &mut SIM) -> (PitChannel0, PitChannel1) {
+        sim.scgc6.modify(|_, w| w.pit().set_bit());
+        // Enable the module, the timers keep running in debug mode.
+        self.mcr.write(|w| w.mdis().clear_bit().frz().clear_bit());
+        (PitChannel0 { pit: self, clocks }, PitChannel1 { clocks })
+    }
+
+    fn join(channel0: PitChannel0, _channel1: PitChannel1, sim: &mut SIM) -> Self {
+        let pit = channel0.pit;
+        unsafe {
+            // Disable the channels and their interrupts, including the chaining of channel 1.
+            pit.tctrl0.write(|w| w.bits(0));
+            pit.tctrl1.write(|w| w.bits(0));
+        }
+        // TIF is cleared by writing 1.
+        pit.tflg0.write(|w| w.tif().set_bit());
+        pit.tflg1.write(|w| w.tif().set_bit());
+        pit.mcr.write(|w| w.mdis().set_bit());
+        sim.scgc6.modify(|_, w| w.pit().clear_bit());
+        pit
+    }
+}
+
+/// Channel of the PIT
+pub trait PitChannel {
+    /// Returns the frequency at which the channel counts
+    fn frequency(&self) -> Hertz;
+
+    /// Returns the current value of the down counter
+    fn counter() -> u32;
+
+    /// Starts the channel as free-running counter from 0xffffffff down to 0
+    ///
+    /// The interrupt of the channel is disabled.
+    fn run_free(&mut self);
+
+    /// Stops the channel
+    fn stop(&mut self);
+}
+
+macro_rules! pit_channel {
+    ($PitChannelX:ident, $ldval:ident, $cval:ident, $tctrl:ident, $tflg:ident,
+     { $($field:ident: $Field:ty),* }) => {
+        /// Channel of the PIT
+        pub struct $PitChannelX {
+            $($field: $Field,)*
+            clocks: Clocks,
+        }
+
+        impl $PitChannelX {
+            fn pit(&self) -> &pit::RegisterBlock {
+                // NOTE(unsafe) the channel only accesses its own registers
+                unsafe { &*PIT::ptr() }
+            }
+        }
+
+        impl PitChannel for $PitChannelX {
+            fn frequency(&self) -> Hertz {
+                self.clocks.busclk()
+            }
+
+            fn counter() -> u32 {
+                // NOTE(unsafe) atomic read with no side effects
+                unsafe { (*PIT::ptr()).$cval.read().bits() }
+            }
+
+            fn run_free(&mut self) {
+                let pit = self.pit();
+                // Nobody handles the interrupt of a free-running counter, so it is disabled.
+                pit.$tctrl.modify(|_, w| w.ten().clear_bit().tie().clear_bit());
+                pit.$tflg.write(|w| w.tif().set_bit());
+                unsafe {
+                    pit.$ldval.write(|w| w.bits(0xffffffff));
+                }
+                pit.$tctrl.modify(|_, w| w.ten().set_bit());
+            }
+
+            fn stop(&mut self) {
+                self.pit().$tctrl.modify(|_, w| w.ten().clear_bit());
+            }
+        }
+
+        impl CountDown for $PitChannelX {
+            type Time = Hertz;
+
+            fn start<T>(&mut self, timeout: T)
+            where
+                T: Into<Hertz>,
+            {
+                let pit = self.pit();
+                let compare = self.clocks.busclk().0 / timeout.into().0;
+                unsafe {
+                    pit.$ldval.write(|w| w.bits(compare));
+                    // Restart the channel without touching its interrupt enable.
+                    pit.$tctrl.modify(|_, w| w.ten().clear_bit());
+                    pit.$tflg.write(|w| w.tif().set_bit());
+                    pit.$tctrl.modify(|_, w| w.ten().set_bit());
+                }
+            }
+
+            fn wait(&mut self) -> nb::Result<(), Void> {
+                if self.is_pending(Event::Update) {
+                    self.clear_interrupt(Event::Update);
+                    Ok(())
+                } else {
+                    Err(nb::Error::WouldBlock)
+                }
+            }
+        }
+
+        impl Periodic for $PitChannelX {}
+
+        impl TimerInterrupt for $PitChannelX {
+            fn enable_interrupt(&self) {
+                self.pit().$tctrl.modify(|_, w| w.tie().set_bit());
+            }
+            fn disable_interrupt(&self) {
+                self.pit().$tctrl.modify(|_, w| w.tie().clear_bit());
+            }
+
+            fn listen(&mut self, event: Event) {
+                match event {
+                    Event::Update => self.enable_interrupt(),
+                }
+            }
+
+            fn unlisten(&mut self, event: Event) {
+                match event {
+                    Event::Update => self.disable_interrupt(),
+                }
+            }
+
+            fn clear_interrupt(&mut self, event: Event) {
+                match event {
+                    // TIF is cleared by writing 1.
+                    Event::Update => self.pit().$tflg.write(|w| w.tif().set_bit()),
+                }
+            }
+
+            fn is_pending(&self, event: Event) -> bool {
+                match event {
+                    Event::Update => self.pit().$tflg.read().tif().bit_is_set(),
+                }
+            }
+        }
+    };
+}
+
+// Channel 0 owns the PIT until the channels are joined again.
+pit_channel!(PitChannel0, ldval0, cval0, tctrl0, tflg0, { pit: PIT });
+pit_channel!(PitChannel1, ldval1, cval1, tctrl1, tflg1, {});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::U32Ext;
+
+    #[test]
+    fn test_lptmr_dividers() {
+        // 1 kHz LPO.
+        assert_eq!(get_lptmr_dividers(1_000.hz(), 1_000_000), (None, 999));
+        assert_eq!(get_lptmr_dividers(1_000.hz(), 65_536_000), (None, 0xffff));
+        assert_eq!(
+            get_lptmr_dividers(1_000.hz(), 100_000_000),
+            (Some(0), 49_999)
+        );
+        // Timeouts of several hours.
+        let hours = |h: u32| {
+            let timeout: MicroSeconds64 = (h * 3_600).s().into();
+            timeout.0
+        };
+        assert_eq!(get_lptmr_dividers(1_000.hz(), hours(3)), (Some(7), 42_187));
+        assert_eq!(
+            get_lptmr_dividers(1_000.hz(), hours(24)),
+            (Some(10), 42_187)
+        );
+        // Timeouts shorter than one tick wait for one tick.
+        assert_eq!(get_lptmr_dividers(1_000.hz(), 0), (None, 0));
+        assert_eq!(get_lptmr_dividers(1_000.hz(), 500), (None, 0));
+
+        // 32.768 kHz ERCLK32K.
+        assert_eq!(get_lptmr_dividers(32_768.hz(), 1_000_000), (None, 32_767));
+        assert_eq!(
+            get_lptmr_dividers(32_768.hz(), 10_000_000),
+            (Some(2), 40_959)
+        );
+
+        // 8 MHz OSCERCLK.
+        assert_eq!(get_lptmr_dividers(8_000_000.hz(), 100), (None, 799));
+        assert_eq!(
+            get_lptmr_dividers(8_000_000.hz(), 1_000_000),
+            (Some(6), 62_499)
+        );
+        // Too long, clamped to the longest period.
+        assert_eq!(
+            get_lptmr_dividers(8_000_000.hz(), 0xffff_ffff),
+            (Some(15), 0xffff)
+        );
+        // The number of ticks does not fit into 64 bits.
+        let longest: MicroSeconds64 = 0xffff_ffff.ms().into();
+        assert_eq!(
+            get_lptmr_dividers(8_000_000.hz(), longest.0),
+            (Some(15), 0xffff)
+        );
+        assert_eq!(
+            get_lptmr_dividers(8_000_000.hz(), u64::MAX),
+            (Some(15), 0xffff)
+        );
+
+        // 4 MHz fast IRC.
+        assert_eq!(get_lptmr_dividers(4_000_000.hz(), 10), (None, 39));
     }
 }